@@ -0,0 +1,175 @@
+use crate::puzzle::{SudokuPuzzle, EMPTY_SPACE};
+
+const PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+/*
+    The smallest grid that can be reached from `grid` by relabeling digits, transposing,
+    swapping bands, stacks, and rows or columns within them
+
+    Two grids are the same puzzle in disguise exactly when their canonical forms match. Grids are
+    compared cell by cell with blanks first, after relabeling digits in the order they first
+    appear, so the canonical form always starts with a 1 in its first given.
+
+    Every arrangement of the columns is tried, then the rows are picked one at a time, giving up
+    on any choice of row that already makes the grid bigger than the best one found so far
+*/
+pub fn canonical_form(grid: &SudokuPuzzle) -> SudokuPuzzle {
+    let mut cells = [0; 81];
+    for (cell, c) in cells.iter_mut().zip(grid.iter()) {
+        *cell = c.to_digit(10).unwrap_or(0) as u8;
+    }
+
+    let mut search = Search {
+        grid: [0; 81],
+        best: [u8::MAX; 81],
+        current: [0; 81],
+    };
+    for transposed in [false, true] {
+        let cells = if transposed { transpose(&cells) } else { cells };
+        for stacks in PERMUTATIONS {
+            for first in PERMUTATIONS {
+                for second in PERMUTATIONS {
+                    for third in PERMUTATIONS {
+                        let within = [first, second, third];
+                        for row in 0..9 {
+                            for col in 0..9 {
+                                let stack = stacks[col / 3];
+                                let from = stack * 3 + within[stack][col % 3];
+                                search.grid[row * 9 + col] = cells[row * 9 + from];
+                            }
+                        }
+                        search.pick_rows(0, 0, [0; 10], 1);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut canonical = [EMPTY_SPACE; 81];
+    for (c, cell) in canonical.iter_mut().zip(search.best.iter()) {
+        if *cell != 0 {
+            *c = char::from_digit(*cell as u32, 10).unwrap();
+        }
+    }
+    canonical
+}
+
+struct Search {
+    // the grid with its columns already rearranged, 0 for blanks
+    grid: [u8; 81],
+    best: [u8; 81],
+    current: [u8; 81],
+}
+
+impl Search {
+    /*
+        Fill row `position` of `current` with each row of `grid` that can legally go there
+
+        `used` has a bit set for every row of `grid` already placed and `labels` maps digits to
+        their new label
+    */
+    fn pick_rows(&mut self, position: usize, used: u16, labels: [u8; 10], next_label: u8) {
+        if position == 9 {
+            self.best = self.current;
+            return;
+        }
+
+        for row in 0..9 {
+            if used & (1 << row) != 0 || !self.fits(position, row, used) {
+                continue;
+            }
+
+            let mut labels = labels;
+            let mut next_label = next_label;
+            for col in 0..9 {
+                let digit = self.grid[row * 9 + col] as usize;
+                if digit != 0 && labels[digit] == 0 {
+                    labels[digit] = next_label;
+                    next_label += 1;
+                }
+                self.current[position * 9 + col] = labels[digit];
+            }
+
+            // `best` can change further down, so always compare everything placed so far
+            let placed = (position + 1) * 9;
+            if self.current[..placed] <= self.best[..placed] {
+                self.pick_rows(position + 1, used | (1 << row), labels, next_label);
+            }
+        }
+    }
+
+    /*
+        Rows have to stay in their band, so the first row of each band can come from any band
+        that hasn't been used yet and the other two have to come from the same band
+    */
+    fn fits(&self, position: usize, row: usize, used: u16) -> bool {
+        let band_rows = 0b111 << (row / 3 * 3);
+        if position.is_multiple_of(3) {
+            used & band_rows == 0
+        } else {
+            used & band_rows != 0
+        }
+    }
+}
+
+fn transpose(cells: &[u8; 81]) -> [u8; 81] {
+    let mut transposed = [0; 81];
+    for i in 0..9 {
+        for j in 0..9 {
+            transposed[j * 9 + i] = cells[i * 9 + j];
+        }
+    }
+    transposed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::parse_classic_grid;
+    use crate::puzzle_transformer::Transform;
+    use rand::thread_rng;
+
+    const PUZZLE: &str =
+        "..27.4..6......5....1.6...35.....9...4.....15.13...6..374...2....6.2549.........1";
+    const SOLUTION: &str =
+        "932754186768132549451968723527816934649273815813549672374691258186325497295487361";
+
+    #[test]
+    fn disguised_puzzles_match() {
+        let (mut puzzle, mut solution) = (
+            parse_classic_grid(PUZZLE).unwrap(),
+            parse_classic_grid(SOLUTION).unwrap(),
+        );
+        let canonical = canonical_form(&puzzle);
+        let solution_canonical = canonical_form(&solution);
+
+        for _ in 0..3 {
+            Transform::random(&mut thread_rng()).apply((&mut puzzle, &mut solution));
+            assert_eq!(canonical_form(&puzzle), canonical);
+            assert_eq!(canonical_form(&solution), solution_canonical);
+        }
+    }
+
+    #[test]
+    fn different_puzzles_differ() {
+        let puzzle = parse_classic_grid(PUZZLE).unwrap();
+        let mut other = puzzle;
+        other[0] = '9';
+        assert_ne!(canonical_form(&puzzle), canonical_form(&other));
+    }
+
+    #[test]
+    fn canonical_form_is_an_equivalent_grid() {
+        let canonical = canonical_form(&parse_classic_grid(PUZZLE).unwrap());
+        let givens = canonical.iter().filter(|c| **c != EMPTY_SPACE).count();
+        assert_eq!(givens, PUZZLE.chars().filter(|c| *c != '.').count());
+        assert_eq!(canonical_form(&canonical), canonical);
+    }
+}
@@ -0,0 +1,405 @@
+use crate::canonical::canonical_form;
+use crate::daily::Date;
+use crate::formats::{
+    parse_grid, parse_sdm, read_collection, to_line, to_pretty_grid, to_sdm, ImportError,
+};
+use crate::generator;
+use crate::logic::{grade, Technique};
+use crate::puzzle::{Difficulty, Puzzle, PuzzleId, SudokuPuzzle, Variant};
+use crate::shape::{Shape, CLASSIC};
+use crate::solver::{grid_solutions, solutions, Rules};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rand::{thread_rng, Rng};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process;
+
+/*
+    Command line arguments, running without a subcommand is the same as `play`
+*/
+#[derive(Parser)]
+#[command(name = "sudoku-rs", version, about = "Sudoku in the terminal")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub play: PlayArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Play sudoku in the terminal
+    Play(PlayArgs),
+    /// Play the puzzle of the day, everyone gets the same one
+    Daily {
+        /// beginner, easy, medium, hard, expert or diabolical
+        #[arg(short, long, default_value = "medium")]
+        difficulty: Difficulty,
+        /// Play the puzzle from another day, written as YYYY-MM-DD
+        #[arg(long)]
+        date: Option<Date>,
+    },
+    /// Print the solution of each puzzle on one line, in the same format as the puzzle
+    ///
+    /// Puzzles without a unique solution print "no solution" or "multiple solutions" instead.
+    /// With no grids or files, puzzles are read from stdin one per line
+    Solve {
+        /// Puzzles to solve on any board size, with `.`, `0` or `_` for blanks
+        grids: Vec<String>,
+        /// Read puzzles from a .sdk, .sdm or 81 character file, use - for stdin
+        #[arg(short, long)]
+        file: Vec<String>,
+    },
+    /// Generate new puzzles with a unique solution, along with their grade
+    Generate {
+        /// How many puzzles to generate, every one is different
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+        /// beginner, easy, medium, hard, expert or diabolical
+        #[arg(short, long, default_value = "easy")]
+        difficulty: Difficulty,
+        /// How to write the puzzles out
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Line)]
+        format: OutputFormat,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Seed of the first puzzle in hex, the rest count up from it
+        #[arg(short, long, value_parser = parse_seed)]
+        seed: Option<u32>,
+    },
+    /// Print each puzzle once, dropping any that are the same as an earlier one in disguise
+    ///
+    /// With no grids or files, puzzles are read from stdin one per line
+    Dedupe {
+        /// Puzzles to check, with `.`, `0` or `_` for blanks
+        grids: Vec<String>,
+        /// Read puzzles from a .sdk, .sdm or 81 character file, use - for stdin
+        #[arg(short, long)]
+        file: Vec<String>,
+    },
+    /// Print the difficulty of a puzzle and the hardest technique it needs
+    Grade {
+        /// The puzzle, with `.`, `0` or `_` for blanks
+        grid: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// One puzzle per line, followed by its difficulty and the hardest technique it needs
+    Line,
+    /// SadMan Software .sdm collection, one puzzle per line without grades
+    Sdm,
+    /// ASCII grids with a heading for each puzzle
+    Grid,
+    /// A JSON array with the puzzle, solution and grade of each puzzle
+    Json,
+}
+
+#[derive(Args)]
+pub struct PlayArgs {
+    /// Start a new game at the given difficulty
+    #[arg(short, long)]
+    pub difficulty: Option<Difficulty>,
+    /// Play a puzzle from a .sdk, .sdm or 81 character file, use - to read from stdin
+    #[arg(short, long, conflicts_with = "seed")]
+    pub file: Option<String>,
+    /// Puzzle id to play, such as hard-1a2b3c, the same id always gives the same puzzle
+    #[arg(short, long)]
+    pub seed: Option<String>,
+    /// Play a new puzzle on a 4x4, 6x6, 9x9, 12x12 or 16x16 board
+    #[arg(long, conflicts_with_all = ["file", "seed"])]
+    pub size: Option<Shape>,
+    /// Play a new puzzle with extra rules, killer or diagonal
+    #[arg(long, conflicts_with_all = ["file", "seed"])]
+    pub variant: Option<Variant>,
+}
+
+/*
+    Solve every puzzle given on the command line, in files or on stdin
+
+    Nothing is printed unless all the input can be read, so a typo never leaves a script
+    with half its answers
+*/
+pub fn solve(grids: &[String], files: &[String]) {
+    for puzzle in &read_inputs(grids, files) {
+        println!("{}", solution_line(puzzle));
+    }
+}
+
+/*
+    Print every puzzle that isn't a relabeled, rotated, reflected or shuffled copy of one
+    before it, the number of puzzles dropped goes to stderr
+
+    Canonical forms only work on 9x9 boards, other sizes only drop exact copies
+*/
+pub fn dedupe(grids: &[String], files: &[String]) {
+    let puzzles = read_inputs(grids, files);
+    let mut seen = HashSet::new();
+    for puzzle in &puzzles {
+        let key = match SudokuPuzzle::try_from(puzzle.as_slice()) {
+            Ok(grid) => canonical_form(&grid).to_vec(),
+            Err(_) => puzzle.clone(),
+        };
+        if seen.insert(key) {
+            println!("{}", to_line(puzzle));
+        }
+    }
+    eprintln!("Removed {} duplicate puzzles", puzzles.len() - seen.len());
+}
+
+/*
+    Every puzzle from the command line, then from each file, or from stdin if there are neither
+*/
+fn read_inputs(grids: &[String], files: &[String]) -> Vec<Vec<char>> {
+    let mut puzzles = vec![];
+    for grid in grids {
+        puzzles.push(parse_or_exit(grid));
+    }
+    for path in files {
+        match read_grids(path) {
+            Ok(grids) => puzzles.extend(grids),
+            Err(err) => exit_with_error(&format!("{}: {}", path, err)),
+        }
+    }
+    if grids.is_empty() && files.is_empty() {
+        puzzles = read_grids("-").unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    }
+    puzzles
+}
+
+fn solution_line(grid: &[char]) -> String {
+    // the grid was read by `parse_grid`, so it is always the size of some board
+    let shape = Shape::from_cell_count(grid.len()).unwrap_or(CLASSIC);
+    match grid_solutions(Rules::plain(shape), grid, 2).as_slice() {
+        [solution] => to_line(solution),
+        [] => "no solution".to_string(),
+        _ => "multiple solutions".to_string(),
+    }
+}
+
+/*
+    Puzzles are read one per line, from stdin or any file except a .sdk file, which holds a
+    single puzzle across many lines
+*/
+fn read_grids(path: &str) -> Result<Vec<Vec<char>>, ImportError> {
+    let is_sdk = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("sdk"));
+    if is_sdk {
+        return read_collection(path);
+    }
+    let mut text = String::new();
+    let read = match path {
+        "-" => io::stdin().read_to_string(&mut text).map(|_| ()),
+        _ => fs::read_to_string(path).map(|file| text = file),
+    };
+    read.map_err(|err| ImportError::Io(err.to_string()))?;
+    parse_sdm(&text)
+}
+
+/*
+    Generate `count` different puzzles at the difficulty, always with the generator since
+    puzzles made from the seed tables only come in a handful of shapes
+*/
+pub fn generate(
+    count: usize,
+    difficulty: Difficulty,
+    format: OutputFormat,
+    output: Option<&str>,
+    seed: Option<u32>,
+) {
+    let mut id = PuzzleId {
+        shape: CLASSIC,
+        variant: Variant::Classic,
+        difficulty,
+        seed: seed.unwrap_or_else(|| thread_rng().gen()),
+    };
+    let mut seen = HashSet::new();
+    let mut puzzles = vec![];
+
+    // each puzzle gets its own seed, so any one of them can be made again from its id
+    while puzzles.len() < count {
+        let (puzzle, solution) =
+            generator::generate(&difficulty, &mut id.rng()).unwrap_or_else(|| {
+                exit_with_error(&format!("Could not generate a {} puzzle", difficulty))
+            });
+        // puzzles that are only disguised copies of each other count as the same
+        if seen.insert(canonical_form(&puzzle)) {
+            puzzles.push(Puzzle {
+                shape: CLASSIC,
+                puzzle: puzzle.to_vec(),
+                solution: solution.to_vec(),
+                variant: Variant::Classic,
+                cages: vec![],
+                difficulty,
+                id: Some(id),
+                origin: None,
+            });
+        }
+        id.seed = id.seed.wrapping_add(1);
+    }
+
+    let text = format_puzzles(&puzzles, format);
+    match output {
+        Some(path) => fs::write(path, text)
+            .unwrap_or_else(|err| exit_with_error(&format!("{}: {}", path, err))),
+        None => print!("{}", text),
+    }
+}
+
+fn format_puzzles(puzzles: &[Puzzle], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Line => puzzles
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} {} ({})\n",
+                    to_line(&p.puzzle),
+                    p.difficulty,
+                    technique_of(p)
+                )
+            })
+            .collect(),
+        OutputFormat::Sdm => to_sdm(
+            &puzzles
+                .iter()
+                .filter_map(Puzzle::classic_grid)
+                .collect::<Vec<_>>(),
+        ),
+        OutputFormat::Grid => puzzles
+            .iter()
+            .enumerate()
+            .map(|(n, p)| {
+                format!(
+                    "Puzzle {} - #{} {} ({})\n{}\n",
+                    n + 1,
+                    id_of(p),
+                    p.difficulty,
+                    technique_of(p),
+                    to_pretty_grid(&p.puzzle)
+                )
+            })
+            .collect(),
+        OutputFormat::Json => {
+            let entries: Vec<String> = puzzles
+                .iter()
+                .map(|p| {
+                    format!(
+                        "  {{\"id\": \"{}\", \"difficulty\": \"{}\", \"technique\": \"{}\", \"givens\": \"{}\", \"solution\": \"{}\"}}",
+                        id_of(p),
+                        p.difficulty,
+                        technique_of(p),
+                        to_line(&p.puzzle),
+                        to_line(&p.solution)
+                    )
+                })
+                .collect();
+            format!("[\n{}\n]\n", entries.join(",\n"))
+        }
+    }
+}
+
+pub fn grade_puzzle(grid: &str) {
+    let grid: SudokuPuzzle = parse_or_exit(grid)
+        .try_into()
+        .unwrap_or_else(|_| exit_with_error("Only 9x9 puzzles can be graded"));
+    match solutions(&grid, 2).len() {
+        1 => println!("{} ({})", Difficulty::rate(&grid), grade(&grid)),
+        0 => exit_with_error("The puzzle has no solution"),
+        _ => exit_with_error("The puzzle has more than one solution"),
+    }
+}
+
+/*
+    The hardest technique a generated puzzle needs, they are always 9x9 so they can be graded
+*/
+fn technique_of(puzzle: &Puzzle) -> Technique {
+    grade(&puzzle.classic_grid().expect("generated puzzles are 9x9"))
+}
+
+fn id_of(puzzle: &Puzzle) -> String {
+    puzzle.id.map(|id| id.to_string()).unwrap_or_default()
+}
+
+fn parse_seed(seed: &str) -> Result<u32, String> {
+    u32::from_str_radix(seed.trim_start_matches('#'), 16)
+        .map_err(|_| format!("'{}' is not a hex number", seed))
+}
+
+fn parse_or_exit(grid: &str) -> Vec<char> {
+    parse_grid(grid).unwrap_or_else(|err| exit_with_error(&err.to_string()))
+}
+
+pub fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::EMPTY_SPACE;
+
+    #[test]
+    fn solution_lines() {
+        let puzzle = parse_grid(
+            "..27.4..6......5....1.6...35.....9...4.....15.13...6..374...2....6.2549.........1",
+        )
+        .unwrap();
+        assert_eq!(
+            solution_line(&puzzle),
+            "932754186768132549451968723527816934649273815813549672374691258186325497295487361"
+        );
+
+        let mut broken = puzzle;
+        broken[0] = '2';
+        assert_eq!(solution_line(&broken), "no solution");
+        assert_eq!(solution_line(&[EMPTY_SPACE; 81]), "multiple solutions");
+
+        let small = parse_grid("1...2...3...4...").unwrap();
+        assert_eq!(solution_line(&small), "multiple solutions");
+        let small = parse_grid("12.434..2..14...").unwrap();
+        assert_eq!(solution_line(&small), "1234341223414123");
+    }
+
+    #[test]
+    fn text_files_hold_a_puzzle_per_line() {
+        let dir = std::env::temp_dir().join("sudoku-rs-cli-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("two.txt");
+        let line =
+            "..27.4..6......5....1.6...35.....9...4.....15.13...6..374...2....6.2549.........1";
+        fs::write(&path, format!("{}\n{}\n", line, "12.434..2..14...")).unwrap();
+
+        let grids = read_grids(path.to_str().unwrap()).unwrap();
+        assert_eq!(grids.len(), 2);
+        assert_eq!(grids[0], parse_grid(line).unwrap());
+        assert_eq!(solution_line(&grids[1]), "1234341223414123");
+    }
+
+    #[test]
+    fn generated_formats() {
+        let puzzles: Vec<Puzzle> = (0..3)
+            .map(|_| Puzzle::new_puzzle(Difficulty::Beginner))
+            .collect();
+        let grids: Vec<SudokuPuzzle> = puzzles.iter().filter_map(Puzzle::classic_grid).collect();
+
+        let lines = format_puzzles(&puzzles, OutputFormat::Line);
+        assert_eq!(lines.lines().count(), 3);
+        assert!(lines.lines().all(|line| line.contains("Beginner (")));
+
+        let sdm = format_puzzles(&puzzles, OutputFormat::Sdm);
+        let parsed: Vec<Vec<char>> = grids.iter().map(|grid| grid.to_vec()).collect();
+        assert_eq!(parse_sdm(&sdm).unwrap(), parsed);
+
+        let json = format_puzzles(&puzzles, OutputFormat::Json);
+        assert_eq!(json.matches("\"givens\"").count(), 3);
+        assert!(json.starts_with('[') && json.trim_end().ends_with(']'));
+    }
+}
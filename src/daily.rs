@@ -0,0 +1,148 @@
+use crate::puzzle::{Difficulty, PuzzleId, Variant};
+use crate::shape::CLASSIC;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/*
+    A calendar date, only used to pick the daily puzzle so there is no need for a date crate
+*/
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Date {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /*
+        Today's date in UTC, so everyone gets the same puzzle no matter where they are
+    */
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        Date::from_days((seconds / SECONDS_PER_DAY) as i64)
+    }
+
+    /*
+        Convert days since 1970-01-01 to a date, using the algorithm from
+        http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    */
+    fn from_days(days: i64) -> Date {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Date {
+            year: year as u32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+
+    /*
+        How many days there are in a month, February has 29 in leap years
+    */
+    fn days_in_month(year: u32, month: u32) -> u32 {
+        let leap_year =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        match month {
+            2 if leap_year => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /*
+        The id of the daily puzzle, the seed is just the date written as a number
+    */
+    pub fn puzzle_id(&self, difficulty: Difficulty) -> PuzzleId {
+        PuzzleId {
+            shape: CLASSIC,
+            variant: Variant::Classic,
+            difficulty,
+            seed: self.year * 10_000 + self.month * 100 + self.day,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /*
+        Parse a date written as YYYY-MM-DD
+    */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid date '{}', expected YYYY-MM-DD", s);
+        let parts: Vec<u32> = s
+            .trim()
+            .split('-')
+            .map(|part| part.parse().map_err(|_| error()))
+            .collect::<Result<_, _>>()?;
+
+        // years past 9999 don't fit in YYYY, nor in the seed the date becomes
+        match parts.as_slice() {
+            &[year, month, day]
+                if (1..=9999).contains(&year)
+                    && (1..=12).contains(&month)
+                    && (1..=Date::days_in_month(year, month)).contains(&day) =>
+            {
+                Ok(Date { year, month, day })
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_from_days() {
+        assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(Date::from_days(11_016).to_string(), "2000-02-29");
+        assert_eq!(Date::from_days(20_744).to_string(), "2026-10-18");
+    }
+
+    #[test]
+    fn parse_dates() {
+        let date: Date = "2026-10-18".parse().unwrap();
+        assert_eq!(date.to_string(), "2026-10-18");
+        assert_eq!(date.puzzle_id(Difficulty::Hard).seed, 20_261_018);
+        assert!("2026-13-01".parse::<Date>().is_err());
+        assert!("2026-02-31".parse::<Date>().is_err());
+        assert!("2025-04-31".parse::<Date>().is_err());
+        assert!("2025-02-29".parse::<Date>().is_err());
+        assert!("1900-02-29".parse::<Date>().is_err());
+        assert!("2024-02-29".parse::<Date>().is_ok());
+        assert!("2000-02-29".parse::<Date>().is_ok());
+        assert!("2025-12-31".parse::<Date>().is_ok());
+        assert!("999999-01-01".parse::<Date>().is_err());
+        assert!("0000-01-01".parse::<Date>().is_err());
+        assert!("9999-12-31".parse::<Date>().is_ok());
+        assert!("yesterday".parse::<Date>().is_err());
+    }
+}
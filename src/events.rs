@@ -1,4 +1,4 @@
-use std::{io, sync::mpsc, thread, time::Duration};
+use std::{sync::mpsc, thread, time::Duration};
 use termion::{event::Key, input::TermRead};
 
 pub const TICK_RATE_MS: u64 = 250;
@@ -41,8 +41,15 @@ impl Events {
             _input_handle: {
                 let tx = tx.clone();
                 thread::spawn(move || {
-                    let stdin = io::stdin();
-                    for key in stdin.keys().flatten() {
+                    // read keys from the terminal itself, stdin may have been used to pipe in a puzzle
+                    let tty = match termion::get_tty() {
+                        Ok(tty) => tty,
+                        Err(err) => {
+                            eprintln!("{}", err);
+                            return;
+                        }
+                    };
+                    for key in tty.keys().flatten() {
                         if let Err(err) = tx.send(Event::Input(key)) {
                             eprintln!("{}", err);
                             return;
@@ -0,0 +1,493 @@
+use crate::generator::difficulty_of_givens;
+use crate::puzzle::{count_givens, Difficulty, Puzzle, SudokuPuzzle, Variant, EMPTY_SPACE};
+use crate::shape::{Shape, CLASSIC, SHAPES};
+use crate::solver::{grid_solutions, Rules};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+// characters used to draw grids around puzzles, these are skipped when reading
+const DECORATIONS: [char; 5] = ['|', '-', '+', '=', ':'];
+
+#[derive(Debug, PartialEq)]
+pub enum ImportError {
+    Io(String),
+    InvalidCharacter(char),
+    WrongLength(usize),
+    NoSolution,
+    MultipleSolutions,
+    EmptyCollection,
+    AtLine(usize, Box<ImportError>),
+    InPuzzle(usize, Box<ImportError>),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "Could not read puzzle: {}", err),
+            ImportError::InvalidCharacter(c) => write!(f, "Invalid character '{}' in puzzle", c),
+            ImportError::WrongLength(len) => {
+                let sizes: Vec<String> =
+                    SHAPES.iter().map(|s| s.cell_count().to_string()).collect();
+                write!(f, "Puzzle has {} cells, expected {}", len, sizes.join(", "))
+            }
+            ImportError::NoSolution => write!(f, "Puzzle has no solution"),
+            ImportError::MultipleSolutions => write!(f, "Puzzle has more than one solution"),
+            ImportError::EmptyCollection => write!(f, "No puzzles found"),
+            ImportError::AtLine(line, err) => write!(f, "Line {}: {}", line, err),
+            ImportError::InPuzzle(n, err) => write!(f, "Puzzle {}: {}", n, err),
+        }
+    }
+}
+
+/*
+    Parse a grid in the common 81 character format, read left to right and top to bottom
+
+    Digits 1-9 are givens and '.', '0' or '_' are blanks. Whitespace and grid decoration
+    such as '|', '-' and '+' can appear anywhere and is ignored. Grids of 16, 36, 144 or 256
+    cells are read as the other board sizes, which carry on with the letters A-G for 10 and up
+*/
+pub fn parse_grid(text: &str) -> Result<Vec<char>, ImportError> {
+    let mut cells = vec![];
+    for c in text.chars() {
+        match c.to_ascii_uppercase() {
+            c @ ('1'..='9' | 'A'..='G') => cells.push(c),
+            '.' | '0' | '_' => cells.push(EMPTY_SPACE),
+            c if c.is_whitespace() || DECORATIONS.contains(&c) => {}
+            _ => return Err(ImportError::InvalidCharacter(c)),
+        }
+    }
+
+    let shape = Shape::from_cell_count(cells.len()).ok_or(ImportError::WrongLength(cells.len()))?;
+    // a letter is only a digit on the boards big enough to need it
+    match cells
+        .iter()
+        .find(|c| **c != EMPTY_SPACE && shape.digit_of(**c).is_none())
+    {
+        Some(c) => Err(ImportError::InvalidCharacter(*c)),
+        None => Ok(cells),
+    }
+}
+
+/*
+    Parse a grid that has to be 9x9, for the formats and tools that only work on classic boards
+*/
+pub fn parse_classic_grid(text: &str) -> Result<SudokuPuzzle, ImportError> {
+    let cells = parse_grid(text)?;
+    let len = cells.len();
+    cells.try_into().map_err(|_| ImportError::WrongLength(len))
+}
+
+/*
+    Turn a grid into a playable puzzle, the grid must have exactly one solution
+*/
+pub fn puzzle_from_grid(grid: Vec<char>) -> Result<Puzzle, ImportError> {
+    let shape = Shape::from_cell_count(grid.len()).ok_or(ImportError::WrongLength(grid.len()))?;
+    match grid_solutions(Rules::plain(shape), &grid, 2).as_slice() {
+        [solution] => Ok(Puzzle {
+            shape,
+            solution: solution.clone(),
+            variant: Variant::Classic,
+            cages: vec![],
+            // only 9x9 boards can be graded, other sizes go by their share of givens
+            difficulty: match SudokuPuzzle::try_from(grid.as_slice()) {
+                Ok(classic) => Difficulty::rate(&classic),
+                Err(_) => difficulty_of_givens(shape, count_givens(&grid)),
+            },
+            puzzle: grid,
+            id: None,
+            origin: None,
+        }),
+        [] => Err(ImportError::NoSolution),
+        _ => Err(ImportError::MultipleSolutions),
+    }
+}
+
+/*
+    Read every puzzle in a file, the format is picked from the extension
+
+    .sdm files hold one puzzle per line, .sdk files hold a single puzzle with metadata,
+    anything else is read as a single grid in the 81 character format
+*/
+pub fn read_collection(path: &str) -> Result<Vec<Vec<char>>, ImportError> {
+    let text = read_input(path).map_err(|err| ImportError::Io(err.to_string()))?;
+    let extension = Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+
+    let grids = match extension.as_deref() {
+        Some("sdm") => parse_sdm(&text)?,
+        Some("sdk") => vec![parse_sdk(&text)?.grid.to_vec()],
+        _ => vec![parse_grid(&text)?],
+    };
+    if grids.is_empty() {
+        return Err(ImportError::EmptyCollection);
+    }
+    Ok(grids)
+}
+
+fn read_input(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+/*
+    Write a grid on a single line, blanks are written as '.'
+*/
+pub fn to_line(grid: &[char]) -> String {
+    grid.iter()
+        .map(|c| if *c == EMPTY_SPACE { '.' } else { *c })
+        .collect()
+}
+
+/*
+    Draw a grid with lines between the boxes, it can be read back in by `parse_grid`
+*/
+pub fn to_pretty_grid(grid: &[char]) -> String {
+    let shape = Shape::from_cell_count(grid.len()).unwrap_or(CLASSIC);
+    let stack = "-".repeat(shape.box_cols * 2 + 1);
+    let border = format!("+{}+\n", vec![stack; shape.box_rows].join("+"));
+    let mut text = String::new();
+    let line: Vec<char> = to_line(grid).chars().collect();
+    for (row, cells) in line.chunks(shape.size()).enumerate() {
+        if row % shape.box_rows == 0 {
+            text.push_str(&border);
+        }
+        for (col, c) in cells.iter().enumerate() {
+            if col % shape.box_cols == 0 {
+                text.push_str("| ");
+            }
+            text.push(*c);
+            text.push(' ');
+        }
+        text.push_str("|\n");
+    }
+    text.push_str(&border);
+    text
+}
+
+pub fn to_json(puzzle: &Puzzle, displayed_puzzle: &[char]) -> String {
+    // where the puzzle came from, if we know, so it can be made again
+    let mut source = String::new();
+    if let Some(id) = puzzle.id {
+        source.push_str(&format!("  \"id\": \"{}\",\n", id));
+    }
+    if let Some(origin) = puzzle.origin {
+        source.push_str(&format!("  \"origin\": \"{}\",\n", origin));
+    }
+
+    // the rules on top of plain sudoku, cages are written as `sum:cell.cell` counting from 0
+    let mut rules = String::new();
+    if puzzle.variant != Variant::Classic {
+        rules.push_str(&format!("  \"variant\": \"{}\",\n", puzzle.variant));
+    }
+    if !puzzle.cages.is_empty() {
+        let cages: Vec<String> = puzzle.cages.iter().map(|c| format!("\"{}\"", c)).collect();
+        rules.push_str(&format!("  \"cages\": [{}],\n", cages.join(", ")));
+    }
+
+    format!(
+        "{{\n{}{}  \"difficulty\": \"{}\",\n  \"givens\": \"{}\",\n  \"current\": \"{}\",\n  \"solution\": \"{}\"\n}}\n",
+        source,
+        rules,
+        puzzle.difficulty,
+        to_line(&puzzle.puzzle),
+        to_line(displayed_puzzle),
+        to_line(&puzzle.solution)
+    )
+}
+
+/*
+    Write the givens, the board as the player currently has it and the solution to `dir`,
+    once as lines, once as pretty grids and once as JSON, plus a .sdk file for 9x9 boards.
+    Returns the files written
+*/
+pub fn export(
+    dir: &Path,
+    puzzle: &Puzzle,
+    displayed_puzzle: &[char],
+) -> io::Result<Vec<PathBuf>> {
+    let boards = [
+        ("Givens", puzzle.puzzle.as_slice()),
+        ("Current", displayed_puzzle),
+        ("Solution", puzzle.solution.as_slice()),
+    ];
+
+    let mut lines = String::new();
+    let mut grids = String::new();
+    for (name, board) in boards {
+        lines.push_str(&format!("{:<9} {}\n", format!("{}:", name), to_line(board)));
+        grids.push_str(&format!("{}\n{}\n", name, to_pretty_grid(board)));
+    }
+
+    let mut files = vec![
+        (dir.join("export.txt"), lines),
+        (dir.join("export-grid.txt"), grids),
+        (dir.join("export.json"), to_json(puzzle, displayed_puzzle)),
+    ];
+
+    // .sdk files only hold 9x9 puzzles
+    if let Some(grid) = puzzle.classic_grid() {
+        let mut sdk = SdkPuzzle {
+            grid,
+            metadata: vec![(
+                'D',
+                format!("{} puzzle exported from sudoku-rs", puzzle.difficulty),
+            )],
+        };
+        if let Some(id) = puzzle.id {
+            sdk.metadata.push(('S', format!("sudoku-rs puzzle #{}", id)));
+        }
+        if let Some(origin) = puzzle.origin {
+            sdk.metadata.push(('C', origin.to_string()));
+        }
+        files.insert(0, (dir.join("export.sdk"), to_sdk(&sdk)));
+    }
+
+    fs::create_dir_all(dir)?;
+    let mut written = vec![];
+    for (path, contents) in files {
+        fs::write(&path, contents)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/*
+    A puzzle in the SadMan Software .sdk format
+
+    Metadata lines start with '#' and a one letter code, for example
+    #A author, #D description, #C comment, #B date, #S source, #L level and #U url
+*/
+pub struct SdkPuzzle {
+    pub grid: SudokuPuzzle,
+    pub metadata: Vec<(char, String)>,
+}
+
+pub fn parse_sdk(text: &str) -> Result<SdkPuzzle, ImportError> {
+    let mut metadata = vec![];
+    let mut rows = String::new();
+    // newer versions split the file into sections like [Puzzle] and [State], the givens are
+    // the rows before any section or under [Puzzle]
+    let mut in_puzzle = true;
+    for line in text.lines().map(str::trim) {
+        if let Some(entry) = line.strip_prefix('#') {
+            let mut chars = entry.chars();
+            if let Some(code) = chars.next() {
+                metadata.push((code, chars.as_str().trim().to_string()));
+            }
+        } else if line.starts_with('[') {
+            in_puzzle = line.eq_ignore_ascii_case("[Puzzle]");
+        } else if in_puzzle {
+            rows.push_str(line);
+        }
+    }
+
+    Ok(SdkPuzzle {
+        grid: parse_classic_grid(&rows)?,
+        metadata,
+    })
+}
+
+/*
+    Metadata first, then the grid as 9 lines of 9 characters with '.' for blanks
+*/
+pub fn to_sdk(puzzle: &SdkPuzzle) -> String {
+    let mut text = String::new();
+    for (code, value) in &puzzle.metadata {
+        text.push_str(&format!("#{}{}\n", code, value));
+    }
+    for row in to_line(&puzzle.grid).as_bytes().chunks(9) {
+        text.push_str(&String::from_utf8_lossy(row));
+        text.push('\n');
+    }
+    text
+}
+
+/*
+    Parse a SadMan Software .sdm collection, one puzzle per line. Lines of any board size
+    are read, even though the format itself only has 9x9 puzzles
+*/
+pub fn parse_sdm(text: &str) -> Result<Vec<Vec<char>>, ImportError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| parse_grid(line).map_err(|err| ImportError::AtLine(n + 1, Box::new(err))))
+        .collect()
+}
+
+pub fn to_sdm(grids: &[SudokuPuzzle]) -> String {
+    grids.iter().map(|grid| to_line(grid) + "\n").collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str =
+        "..27.4..6......5....1.6...35.....9...4.....15.13...6..374...2....6.2549.........1";
+
+    #[test]
+    fn parse_line() {
+        let grid = parse_grid(LINE).unwrap();
+        assert_eq!(grid[0], EMPTY_SPACE);
+        assert_eq!(grid[2], '2');
+        assert_eq!(grid[80], '1');
+
+        let zeros = LINE.replace('.', "0");
+        assert_eq!(parse_grid(&zeros), Ok(grid));
+    }
+
+    #[test]
+    fn parse_decorated_grid() {
+        let mut decorated = String::new();
+        for (row, chunk) in LINE.as_bytes().chunks(9).enumerate() {
+            if row % 3 == 0 {
+                decorated.push_str("+-------+-------+-------+\n");
+            }
+            for (col, c) in chunk.iter().enumerate() {
+                if col % 3 == 0 {
+                    decorated.push_str("| ");
+                }
+                decorated.push(*c as char);
+                decorated.push(' ');
+            }
+            decorated.push_str("|\n");
+        }
+        decorated.push_str("+-------+-------+-------+\n");
+
+        assert_eq!(parse_grid(&decorated), parse_grid(LINE));
+    }
+
+    #[test]
+    fn parse_other_sizes() {
+        let puzzle = puzzle_from_grid(parse_grid("12.434..2..14...").unwrap()).unwrap();
+        assert_eq!(puzzle.shape, "4x4".parse().unwrap());
+        assert_eq!(to_line(&puzzle.solution), "1234341223414123");
+
+        let hex = format!("ABCDEFGabcdefg12{}", ".".repeat(240));
+        assert_eq!(parse_grid(&hex).unwrap()[15], '2');
+        assert_eq!(parse_grid(&hex).unwrap()[7], 'A');
+        assert_eq!(
+            parse_grid(&format!("A{}", ".".repeat(80))),
+            Err(ImportError::InvalidCharacter('A'))
+        );
+        assert_eq!(
+            parse_classic_grid("12.434..2..14..."),
+            Err(ImportError::WrongLength(16))
+        );
+    }
+
+    #[test]
+    fn export_formats_round_trip() {
+        let grid = parse_grid(LINE).unwrap();
+        assert_eq!(to_line(&grid), LINE);
+        assert_eq!(parse_grid(&to_pretty_grid(&grid)), Ok(grid));
+    }
+
+    #[test]
+    fn export_json() {
+        let puzzle = puzzle_from_grid(parse_grid(LINE).unwrap()).unwrap();
+        let json = to_json(&puzzle, &puzzle.puzzle);
+        assert!(json.contains(&format!("\"givens\": \"{}\"", LINE)));
+        assert!(json.contains(&format!("\"current\": \"{}\"", LINE)));
+        assert!(json.contains(&format!("\"solution\": \"{}\"", to_line(&puzzle.solution))));
+        assert!(!json.contains("\"origin\""));
+
+        let seeded = Puzzle::new_puzzle(Difficulty::Beginner);
+        let json = to_json(&seeded, &seeded.puzzle);
+        assert!(json.contains(&format!("\"id\": \"{}\"", seeded.id.unwrap())));
+        assert!(!json.contains("\"cages\""));
+    }
+
+    #[test]
+    fn reject_bad_grids() {
+        assert_eq!(parse_grid("123"), Err(ImportError::WrongLength(3)));
+        assert_eq!(
+            parse_grid(&LINE.replace('6', "x")),
+            Err(ImportError::InvalidCharacter('x'))
+        );
+        assert_eq!(
+            puzzle_from_grid(vec![EMPTY_SPACE; 81]).err(),
+            Some(ImportError::MultipleSolutions)
+        );
+
+        let mut conflicting = vec![EMPTY_SPACE; 81];
+        conflicting[0] = '1';
+        conflicting[1] = '1';
+        assert_eq!(
+            puzzle_from_grid(conflicting).err(),
+            Some(ImportError::NoSolution)
+        );
+    }
+
+    #[test]
+    fn import_solves_puzzle() {
+        let puzzle = puzzle_from_grid(parse_grid(LINE).unwrap()).unwrap();
+        assert_eq!(
+            puzzle.solution.iter().collect::<String>(),
+            "932754186768132549451968723527816934649273815813549672374691258186325497295487361"
+        );
+    }
+
+    #[test]
+    fn sdk_round_trip() {
+        let text = "#AMitchel\n#DA beginner puzzle\n\
+            ..27.4..6\n......5..\n..1.6...3\n5.....9..\n.4.....15\n\
+            .13...6..\n374...2..\n..6.2549.\n........1\n";
+        let puzzle = parse_sdk(text).unwrap();
+
+        assert_eq!(puzzle.grid, parse_classic_grid(LINE).unwrap());
+        assert_eq!(
+            puzzle.metadata,
+            vec![
+                ('A', "Mitchel".to_string()),
+                ('D', "A beginner puzzle".to_string())
+            ]
+        );
+        assert_eq!(to_sdk(&puzzle), text);
+    }
+
+    #[test]
+    fn sdk_sections() {
+        let text = "#AMitchel\n[Puzzle]\n\
+            ..27.4..6\n......5..\n..1.6...3\n5.....9..\n.4.....15\n\
+            .13...6..\n374...2..\n..6.2549.\n........1\n\
+            [State]\n\
+            8.27.4..6\n......5..\n..1.6...3\n5.....9..\n.4.....15\n\
+            .13...6..\n374...2..\n..6.2549.\n........1\n";
+        let puzzle = parse_sdk(text).unwrap();
+        assert_eq!(puzzle.grid, parse_classic_grid(LINE).unwrap());
+        assert_eq!(puzzle.metadata, vec![('A', "Mitchel".to_string())]);
+    }
+
+    #[test]
+    fn sdm_round_trip() {
+        let other = LINE.chars().rev().collect::<String>();
+        let text = format!("{}\n\n{}\n", LINE, other);
+        let grids = parse_sdm(&text).unwrap();
+
+        assert_eq!(grids.len(), 2);
+        assert_eq!(grids[1], parse_grid(&other).unwrap());
+        let grids: Vec<SudokuPuzzle> = grids.into_iter().map(|g| g.try_into().unwrap()).collect();
+        assert_eq!(to_sdm(&grids), format!("{}\n{}\n", LINE, other));
+    }
+
+    #[test]
+    fn sdm_reports_bad_line() {
+        let text = format!("{}\n123\n", LINE);
+        assert_eq!(
+            parse_sdm(&text).err(),
+            Some(ImportError::AtLine(
+                2,
+                Box::new(ImportError::WrongLength(3))
+            ))
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use crate::killer::{random_cages, Cage};
+use crate::puzzle::{Difficulty, SudokuPuzzle, EMPTY_SPACE};
+use crate::shape::{Shape, CLASSIC};
+use crate::solver::{count_solutions, solve, Rules};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+const MAX_ATTEMPTS: usize = 100;
+// the fewest givens, as a percentage, left on boards bigger than 9x9
+const MIN_BIG_BOARD_PERCENT: usize = 40;
+
+/*
+    Generate a brand new puzzle for the given difficulty, returning the puzzle and its solution
+
+    A random complete grid is built and then givens are removed one at a time, in a random order,
+    as long as the puzzle keeps a unique solution. Harder puzzles are left with fewer givens, and
+    the result is only kept if grading it gives back the requested difficulty.
+
+    Returns None if no attempt produced a puzzle of the right difficulty
+*/
+pub fn generate<R: Rng>(
+    difficulty: &Difficulty,
+    rng: &mut R,
+) -> Option<(SudokuPuzzle, SudokuPuzzle)> {
+    let target = target_givens(difficulty);
+
+    for _ in 0..MAX_ATTEMPTS {
+        let solution = random_solution(rng);
+        let puzzle = remove_givens(Rules::plain(CLASSIC), &solution, target, rng);
+        let puzzle: SudokuPuzzle = puzzle.try_into().unwrap();
+        if Difficulty::rate(&puzzle) == *difficulty {
+            return Some((puzzle, solution));
+        }
+    }
+    None
+}
+
+/*
+    Generate a puzzle on a board of any shape under the given rules, returning the puzzle
+    and its solution
+
+    Only classic 9x9 puzzles can be graded, so on other boards and variants the difficulty
+    just sets what share of the cells are left as givens
+*/
+pub fn generate_grid<R: Rng>(
+    rules: Rules,
+    difficulty: &Difficulty,
+    rng: &mut R,
+) -> (Vec<char>, Vec<char>) {
+    let solution = random_grid(rules, rng);
+    let target = rules.shape.cell_count() * givens_percent(rules.shape, difficulty) / 100;
+    (remove_givens(rules, &solution, target, rng), solution)
+}
+
+/*
+    Generate a killer puzzle, returning the puzzle, its solution and its cages
+
+    The cage sums carry most of the information, so far fewer givens are kept than on a
+    plain board and the harder difficulties start from an empty grid
+*/
+pub fn generate_killer<R: Rng>(
+    shape: Shape,
+    difficulty: &Difficulty,
+    rng: &mut R,
+) -> (Vec<char>, Vec<char>, Vec<Cage>) {
+    let solution = random_grid(Rules::plain(shape), rng);
+    let cages = random_cages(shape, &solution, rng);
+    let rules = Rules {
+        shape,
+        cages: &cages,
+        diagonal: false,
+    };
+    let target = shape.cell_count() * killer_givens_percent(shape, difficulty) / 100;
+    let puzzle = remove_givens(rules, &solution, target, rng);
+    (puzzle, solution, cages)
+}
+
+/*
+    How many givens a puzzle of each difficulty should be left with
+
+    The harder difficulties remove as many givens as they can, the grade decides where they land
+*/
+fn target_givens(difficulty: &Difficulty) -> usize {
+    match difficulty {
+        Difficulty::Beginner => 36,
+        Difficulty::Easy => 30,
+        _ => 0,
+    }
+}
+
+fn givens_percent(shape: Shape, difficulty: &Difficulty) -> usize {
+    let percent = match difficulty {
+        Difficulty::Beginner => 55,
+        Difficulty::Easy => 50,
+        Difficulty::Medium => 45,
+        Difficulty::Hard => 40,
+        Difficulty::Expert => 35,
+        Difficulty::Diabolical => 0,
+    };
+    // as with killer, proving a nearly empty grid unique takes far too long on the bigger boards
+    if shape.size() > CLASSIC.size() {
+        percent.max(MIN_BIG_BOARD_PERCENT)
+    } else {
+        percent
+    }
+}
+
+/*
+    The difficulty a puzzle that can't be graded would have been generated at, the easiest
+    one that leaves no more givens than it has
+*/
+pub fn difficulty_of_givens(shape: Shape, givens: usize) -> Difficulty {
+    Difficulty::ALL
+        .into_iter()
+        .find(|difficulty| givens * 100 >= shape.cell_count() * givens_percent(shape, difficulty))
+        .unwrap_or(Difficulty::Diabolical)
+}
+
+fn killer_givens_percent(shape: Shape, difficulty: &Difficulty) -> usize {
+    let percent = match difficulty {
+        Difficulty::Beginner => 25,
+        Difficulty::Easy => 15,
+        Difficulty::Medium => 10,
+        Difficulty::Hard => 5,
+        Difficulty::Expert | Difficulty::Diabolical => 0,
+    };
+    // proving a nearly empty killer grid unique takes far too long on the bigger boards
+    if shape.size() > CLASSIC.size() {
+        percent.max(10)
+    } else {
+        percent
+    }
+}
+
+fn random_solution<R: Rng>(rng: &mut R) -> SudokuPuzzle {
+    random_grid(Rules::plain(CLASSIC), rng).try_into().unwrap()
+}
+
+/*
+    Build a random complete grid
+
+    The boxes on the main diagonal don't share any rows or columns, so they can be filled with
+    any random permutation and the solver is then guaranteed to be able to finish a 9x9 grid.
+    When the diagonals have to hold every digit the boxes would clash along them, so the main
+    diagonal itself is filled with a random permutation instead
+*/
+fn random_grid<R: Rng>(rules: Rules, rng: &mut R) -> Vec<char> {
+    let shape = rules.shape;
+    let mut digits: Vec<char> = (1..=shape.size()).map(|d| shape.symbol(d)).collect();
+    let seeded: Vec<Vec<usize>> = if rules.diagonal {
+        let [down, _] = shape.diagonal_units();
+        vec![down]
+    } else {
+        // boxes are stacked `box_cols` high and `box_rows` across
+        let diagonal = shape.box_rows.min(shape.box_cols);
+        (0..diagonal)
+            .map(|n| shape.unit(2 * shape.size() + n * shape.box_rows + n))
+            .collect()
+    };
+
+    loop {
+        let mut grid = vec![EMPTY_SPACE; shape.cell_count()];
+        for unit in &seeded {
+            digits.shuffle(rng);
+            for (cell, digit) in unit.iter().zip(&digits) {
+                grid[*cell] = *digit;
+            }
+        }
+
+        // boxes that are not square or a seeded diagonal can leave a grid that can't be finished,
+        // try again
+        if let Some(solution) = solve(rules, &grid) {
+            return solution;
+        }
+    }
+}
+
+/*
+    Remove givens from the solution in a random order until `target` is reached,
+    skipping any cell whose removal would give the puzzle more than one solution
+*/
+fn remove_givens<R: Rng>(rules: Rules, solution: &[char], target: usize, rng: &mut R) -> Vec<char> {
+    let mut puzzle = solution.to_vec();
+    let mut givens = solution.len();
+    let mut order: Vec<usize> = (0..solution.len()).collect();
+    order.shuffle(rng);
+
+    for i in order {
+        if givens <= target {
+            break;
+        }
+
+        puzzle[i] = EMPTY_SPACE;
+        if count_solutions(rules, &puzzle, 2) == 1 {
+            givens -= 1;
+        } else {
+            puzzle[i] = solution[i];
+        }
+    }
+    puzzle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::count_givens;
+    use crate::shape::SHAPES;
+    use crate::solver::grid_solutions;
+    use rand::{thread_rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn random_solution_is_complete() {
+        let solution = random_solution(&mut thread_rng());
+        assert_eq!(count_givens(&solution), 81);
+        assert_eq!(count_solutions(Rules::plain(CLASSIC), &solution, 2), 1);
+    }
+
+    /*
+        Fixed seeds keep this from failing now and then on the rare runs where the generator
+        can't reach a difficulty and falls back to the seed tables
+    */
+    #[test]
+    fn generated_puzzles_are_unique() {
+        for difficulty in Difficulty::ALL {
+            let mut rng = ChaCha8Rng::seed_from_u64(1);
+            let (puzzle, solution) = generate(&difficulty, &mut rng).unwrap();
+            assert!(Difficulty::rate(&puzzle) == difficulty);
+            assert_eq!(count_solutions(Rules::plain(CLASSIC), &puzzle, 2), 1);
+            assert_eq!(
+                solve(Rules::plain(CLASSIC), &puzzle),
+                Some(solution.to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn every_shape_generates() {
+        for shape in SHAPES {
+            let (puzzle, solution) =
+                generate_grid(Rules::plain(shape), &Difficulty::Easy, &mut thread_rng());
+            assert_eq!(puzzle.len(), shape.cell_count());
+            assert_eq!(
+                grid_solutions(Rules::plain(shape), &puzzle, 2),
+                vec![solution]
+            );
+        }
+    }
+
+    #[test]
+    fn killer_puzzles_are_unique() {
+        let (puzzle, solution, cages) =
+            generate_killer(CLASSIC, &Difficulty::Expert, &mut thread_rng());
+        let rules = Rules {
+            shape: CLASSIC,
+            cages: &cages,
+            diagonal: false,
+        };
+        assert_eq!(grid_solutions(rules, &puzzle, 2), vec![solution]);
+    }
+
+    #[test]
+    fn diagonal_puzzles_are_unique() {
+        for (shape, difficulty) in SHAPES
+            .into_iter()
+            .flat_map(|shape| [(shape, Difficulty::Hard), (shape, Difficulty::Diabolical)])
+        {
+            let rules = Rules {
+                diagonal: true,
+                ..Rules::plain(shape)
+            };
+            let (puzzle, solution) = generate_grid(rules, &difficulty, &mut thread_rng());
+            assert_eq!(grid_solutions(rules, &puzzle, 2), vec![solution.clone()]);
+            for unit in shape.diagonal_units() {
+                let mut digits: Vec<char> = unit.iter().map(|cell| solution[*cell]).collect();
+                digits.sort_unstable();
+                digits.dedup();
+                assert_eq!(digits.len(), shape.size());
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+/*
+    Everything the player can change about a single cell, the digit in it and its pencil marks
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellState {
+    pub value: char,
+    pub notes: u16,
+}
+
+/*
+    One edit to the board, every cell it touched along with its state before and after
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Move {
+    pub changes: Vec<(usize, CellState, CellState)>,
+}
+
+/*
+    Undo and redo stacks of the moves made on the board
+*/
+pub struct History {
+    undo: Vec<Move>,
+    redo: Vec<Move>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+
+    /*
+        Remember a new move, this drops anything that could have been redone
+    */
+    pub fn record(&mut self, change: Move) {
+        if change.changes.is_empty() {
+            return;
+        }
+        self.undo.push(change);
+        self.redo.clear();
+    }
+
+    /*
+        Take back the last move, the caller should restore the `before` state of each change
+    */
+    pub fn undo(&mut self) -> Option<&Move> {
+        let change = self.undo.pop()?;
+        self.redo.push(change);
+        self.redo.last()
+    }
+
+    /*
+        Replay the last undone move, the caller should apply the `after` state of each change
+    */
+    pub fn redo(&mut self) -> Option<&Move> {
+        let change = self.redo.pop()?;
+        self.undo.push(change);
+        self.undo.last()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(cell: usize, from: char, to: char) -> Move {
+        Move {
+            changes: vec![(
+                cell,
+                CellState {
+                    value: from,
+                    notes: 0,
+                },
+                CellState {
+                    value: to,
+                    notes: 0,
+                },
+            )],
+        }
+    }
+
+    #[test]
+    fn undo_then_redo() {
+        let mut history = History::new();
+        history.record(set(0, '_', '1'));
+        history.record(set(1, '_', '2'));
+
+        assert_eq!(history.undo(), Some(&set(1, '_', '2')));
+        assert_eq!(history.undo(), Some(&set(0, '_', '1')));
+        assert_eq!(history.undo(), None);
+
+        assert_eq!(history.redo(), Some(&set(0, '_', '1')));
+        assert_eq!(history.redo(), Some(&set(1, '_', '2')));
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn new_move_clears_redo() {
+        let mut history = History::new();
+        history.record(set(0, '_', '1'));
+        history.undo();
+        history.record(set(0, '_', '3'));
+
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(&set(0, '_', '3')));
+    }
+
+    #[test]
+    fn empty_moves_are_ignored() {
+        let mut history = History::new();
+        history.record(Move { changes: vec![] });
+        assert_eq!(history.undo(), None);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::shape::Shape;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+// the most cells a generated cage can have
+const MAX_CAGE_SIZE: usize = 4;
+
+/*
+    A killer cage, the digits in its cells are all different and add up to `sum`
+*/
+#[derive(PartialEq, Clone, Debug)]
+pub struct Cage {
+    pub cells: Vec<usize>,
+    pub sum: u32,
+}
+
+impl fmt::Display for Cage {
+    /*
+        Written as the sum and then the cells, as in `12:0.1.10`
+    */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells: Vec<String> = self.cells.iter().map(|cell| cell.to_string()).collect();
+        write!(f, "{}:{}", self.sum, cells.join("."))
+    }
+}
+
+impl FromStr for Cage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid cage '{}'", s);
+        let (sum, cells) = s.trim().split_once(':').ok_or_else(error)?;
+        Ok(Cage {
+            sum: sum.parse().map_err(|_| error())?,
+            cells: cells
+                .split('.')
+                .map(|cell| cell.parse().map_err(|_| error()))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/*
+    The cage each cell belongs to, if any, as positions in `cages`
+*/
+pub fn cage_index(shape: Shape, cages: &[Cage]) -> Vec<Option<usize>> {
+    let mut cage_of = vec![None; shape.cell_count()];
+    for (c, cage) in cages.iter().enumerate() {
+        for &cell in &cage.cells {
+            cage_of[cell] = Some(c);
+        }
+    }
+    cage_of
+}
+
+/*
+    Split a solved grid into cages of up to 4 cells, each cage grows from a random cell into
+    neighbouring cells that don't repeat a digit already in it
+*/
+pub fn random_cages<R: Rng>(shape: Shape, solution: &[char], rng: &mut R) -> Vec<Cage> {
+    let size = shape.size();
+    let mut caged = vec![false; shape.cell_count()];
+    let mut order: Vec<usize> = (0..shape.cell_count()).collect();
+    order.shuffle(rng);
+
+    let mut cages = vec![];
+    for start in order {
+        if caged[start] {
+            continue;
+        }
+        let target = rng.gen_range(2..=MAX_CAGE_SIZE);
+        let mut cells = vec![start];
+        caged[start] = true;
+
+        while cells.len() < target {
+            let mut neighbours = vec![];
+            for &cell in &cells {
+                let (row, col) = (cell / size, cell % size);
+                let mut add = |next: usize| {
+                    let repeats = cells.iter().any(|c| solution[*c] == solution[next]);
+                    if !caged[next] && !repeats && !neighbours.contains(&next) {
+                        neighbours.push(next);
+                    }
+                };
+                if row > 0 {
+                    add(cell - size);
+                }
+                if row < size - 1 {
+                    add(cell + size);
+                }
+                if col > 0 {
+                    add(cell - 1);
+                }
+                if col < size - 1 {
+                    add(cell + 1);
+                }
+            }
+
+            // boxed in by other cages, this one stays small
+            match neighbours.choose(rng) {
+                Some(&next) => {
+                    caged[next] = true;
+                    cells.push(next);
+                }
+                None => break,
+            }
+        }
+
+        cells.sort_unstable();
+        let sum = cells
+            .iter()
+            .filter_map(|cell| shape.digit_of(solution[*cell]))
+            .sum::<usize>() as u32;
+        cages.push(Cage { cells, sum });
+    }
+    cages.sort_unstable_by_key(|cage| cage.cells[0]);
+    cages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::CLASSIC;
+    use crate::solver::{solve, Rules};
+    use rand::thread_rng;
+
+    #[test]
+    fn cages_cover_the_grid() {
+        let solution = solve(Rules::plain(CLASSIC), &['_'; 81]).unwrap();
+        let cages = random_cages(CLASSIC, &solution, &mut thread_rng());
+
+        let mut cells: Vec<usize> = cages.iter().flat_map(|cage| cage.cells.clone()).collect();
+        cells.sort_unstable();
+        assert_eq!(cells, (0..81).collect::<Vec<_>>());
+        assert_eq!(cages.iter().map(|cage| cage.sum).sum::<u32>(), 9 * 45);
+        assert!(cages.iter().all(|cage| cage.cells.len() <= MAX_CAGE_SIZE));
+    }
+
+    #[test]
+    fn parse_cages() {
+        let cage: Cage = "12:0.1.10".parse().unwrap();
+        assert_eq!(
+            cage,
+            Cage {
+                cells: vec![0, 1, 10],
+                sum: 12
+            }
+        );
+        assert_eq!(cage.to_string(), "12:0.1.10");
+        assert!("12".parse::<Cage>().is_err());
+        assert!("x:1.2".parse::<Cage>().is_err());
+    }
+}
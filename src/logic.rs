@@ -0,0 +1,756 @@
+use crate::puzzle::SudokuPuzzle;
+use crate::solver::{
+    all_units, bit, candidates, digit_of, sees, unit_indices, Rules, ALL_CANDIDATES,
+};
+use std::fmt;
+
+const BOARD_LENGTH: usize = 9;
+const BOARD_SIZE: usize = BOARD_LENGTH * BOARD_LENGTH;
+
+// longest chain of bivalue cells the XY-Chain search will follow
+const MAX_CHAIN_LENGTH: usize = 12;
+
+/*
+    The human solving techniques we know about, ordered from easiest to hardest
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    NakedPair,
+    HiddenPair,
+    Pointing,
+    BoxLineReduction,
+    XWing,
+    Swordfish,
+    XYWing,
+    XYChain,
+    // none of the techniques above make progress, the puzzle can only be finished by guessing
+    TrialAndError,
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Technique::NakedSingle => write!(f, "Naked single"),
+            Technique::HiddenSingle => write!(f, "Hidden single"),
+            Technique::NakedPair => write!(f, "Naked pair"),
+            Technique::HiddenPair => write!(f, "Hidden pair"),
+            Technique::Pointing => write!(f, "Pointing"),
+            Technique::BoxLineReduction => write!(f, "Box/line reduction"),
+            Technique::XWing => write!(f, "X-Wing"),
+            Technique::Swordfish => write!(f, "Swordfish"),
+            Technique::XYWing => write!(f, "XY-Wing"),
+            Technique::XYChain => write!(f, "XY-Chain"),
+            Technique::TrialAndError => write!(f, "Trial and error"),
+        }
+    }
+}
+
+/*
+    A single logical deduction, either placing digits or removing candidates from cells
+
+    `cells` are the cells the deduction was based on, `description` explains it in one line
+*/
+pub struct Step {
+    pub technique: Technique,
+    pub placements: Vec<(usize, u8)>,
+    pub eliminations: Vec<(usize, u8)>,
+    pub cells: Vec<usize>,
+    pub description: String,
+}
+
+/*
+    The state a human solver keeps track of, the placed digits and the pencil marks for every empty cell
+*/
+#[derive(Clone, Copy)]
+pub struct Grid {
+    values: [u8; BOARD_SIZE],
+    candidates: [u16; BOARD_SIZE],
+}
+
+impl Grid {
+    pub fn from_puzzle(puzzle: &SudokuPuzzle) -> Grid {
+        let mut grid = Grid {
+            values: [0; BOARD_SIZE],
+            candidates: [ALL_CANDIDATES; BOARD_SIZE],
+        };
+        for (i, c) in puzzle.iter().enumerate() {
+            if let Some(digit) = c.to_digit(10).filter(|d| *d != 0) {
+                grid.place(i, digit as u8);
+            }
+        }
+        grid
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(|v| *v != 0)
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        for &(cell, digit) in &step.placements {
+            self.place(cell, digit);
+        }
+        for &(cell, digit) in &step.eliminations {
+            self.candidates[cell] &= !bit(digit);
+        }
+    }
+
+    /*
+        Find the simplest deduction that can be made from the current state
+    */
+    pub fn next_step(&self) -> Option<Step> {
+        self.naked_single()
+            .or_else(|| self.hidden_single())
+            .or_else(|| self.naked_pair())
+            .or_else(|| self.hidden_pair())
+            .or_else(|| self.pointing())
+            .or_else(|| self.box_line_reduction())
+            .or_else(|| self.fish(2))
+            .or_else(|| self.fish(3))
+            .or_else(|| self.xy_wing())
+            .or_else(|| self.xy_chain())
+    }
+
+    fn place(&mut self, cell: usize, digit: u8) {
+        self.values[cell] = digit;
+        self.candidates[cell] = 0;
+        for other in 0..BOARD_SIZE {
+            if sees(cell, other) {
+                self.candidates[other] &= !bit(digit);
+            }
+        }
+    }
+
+    /*
+        Empty cells in the unit that still have `digit` as a candidate
+    */
+    fn cells_with(&self, unit: &[usize], digit: u8) -> Vec<usize> {
+        unit.iter()
+            .copied()
+            .filter(|&i| self.candidates[i] & bit(digit) != 0)
+            .collect()
+    }
+
+    /*
+        Collect every (cell, digit) elimination for the cells that still have the digit as a candidate
+    */
+    fn eliminations(&self, cells: impl Iterator<Item = usize>, digits: u16) -> Vec<(usize, u8)> {
+        let mut eliminations = vec![];
+        for cell in cells {
+            for digit in digits_in(self.candidates[cell] & digits) {
+                eliminations.push((cell, digit));
+            }
+        }
+        eliminations
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        (0..BOARD_SIZE)
+            .find(|&i| self.values[i] == 0 && self.candidates[i].count_ones() == 1)
+            .map(|i| {
+                let digit = digit_of(self.candidates[i]);
+                Step {
+                    technique: Technique::NakedSingle,
+                    placements: vec![(i, digit)],
+                    eliminations: vec![],
+                    cells: vec![i],
+                    description: format!("Naked single: {} can only be {}", cell_name(i), digit),
+                }
+            })
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for (u, unit) in all_units().iter().enumerate() {
+            for digit in 1..=BOARD_LENGTH as u8 {
+                let cells = self.cells_with(unit, digit);
+                if cells.len() == 1 {
+                    return Some(Step {
+                        technique: Technique::HiddenSingle,
+                        placements: vec![(cells[0], digit)],
+                        eliminations: vec![],
+                        cells,
+                        description: format!("Hidden single: {} in {}", digit, unit_name(u)),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_pair(&self) -> Option<Step> {
+        for (u, unit) in all_units().iter().enumerate() {
+            let pairs: Vec<usize> = unit
+                .iter()
+                .copied()
+                .filter(|&i| self.candidates[i].count_ones() == 2)
+                .collect();
+
+            for (n, &a) in pairs.iter().enumerate() {
+                for &b in &pairs[n + 1..] {
+                    let mask = self.candidates[a];
+                    if self.candidates[b] != mask {
+                        continue;
+                    }
+                    let others = unit.iter().copied().filter(|&i| i != a && i != b);
+                    let eliminations = self.eliminations(others, mask);
+                    if !eliminations.is_empty() {
+                        let digits = digits_in(mask);
+                        return Some(Step {
+                            technique: Technique::NakedPair,
+                            placements: vec![],
+                            eliminations,
+                            cells: vec![a, b],
+                            description: format!(
+                                "Naked pair: {} and {} in {}",
+                                digits[0],
+                                digits[1],
+                                unit_name(u)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn hidden_pair(&self) -> Option<Step> {
+        for (u, unit) in all_units().iter().enumerate() {
+            for first in 1..=BOARD_LENGTH as u8 {
+                let cells = self.cells_with(unit, first);
+                if cells.len() != 2 {
+                    continue;
+                }
+                for second in first + 1..=BOARD_LENGTH as u8 {
+                    if self.cells_with(unit, second) != cells {
+                        continue;
+                    }
+                    let others = !(bit(first) | bit(second)) & ALL_CANDIDATES;
+                    let eliminations = self.eliminations(cells.iter().copied(), others);
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::HiddenPair,
+                            placements: vec![],
+                            eliminations,
+                            cells,
+                            description: format!(
+                                "Hidden pair: {} and {} in {}",
+                                first,
+                                second,
+                                unit_name(u)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /*
+        A digit in a box that is confined to one row or column can be removed from the rest of that line
+    */
+    fn pointing(&self) -> Option<Step> {
+        let units = all_units();
+        for square in 2 * BOARD_LENGTH..3 * BOARD_LENGTH {
+            for digit in 1..=BOARD_LENGTH as u8 {
+                let cells = self.cells_with(&units[square], digit);
+                if cells.len() < 2 {
+                    continue;
+                }
+                for line in lines_containing(&cells) {
+                    let outside = units[line].iter().copied().filter(|i| !cells.contains(i));
+                    let eliminations = self.eliminations(outside, bit(digit));
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::Pointing,
+                            placements: vec![],
+                            eliminations,
+                            cells,
+                            description: format!(
+                                "Pointing: {} in {} is locked to {}",
+                                digit,
+                                unit_name(square),
+                                unit_name(line)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /*
+        A digit in a row or column that is confined to one box can be removed from the rest of that box
+    */
+    fn box_line_reduction(&self) -> Option<Step> {
+        let units = all_units();
+        for line in 0..2 * BOARD_LENGTH {
+            for digit in 1..=BOARD_LENGTH as u8 {
+                let cells = self.cells_with(&units[line], digit);
+                if cells.len() < 2 {
+                    continue;
+                }
+                let square = 2 * BOARD_LENGTH + unit_indices(cells[0]).2;
+                if cells
+                    .iter()
+                    .any(|&i| 2 * BOARD_LENGTH + unit_indices(i).2 != square)
+                {
+                    continue;
+                }
+                let outside = units[square].iter().copied().filter(|i| !cells.contains(i));
+                let eliminations = self.eliminations(outside, bit(digit));
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::BoxLineReduction,
+                        placements: vec![],
+                        eliminations,
+                        cells,
+                        description: format!(
+                            "Box/line reduction: {} in {} is locked to {}",
+                            digit,
+                            unit_name(line),
+                            unit_name(square)
+                        ),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /*
+        X-Wing (size 2) and Swordfish (size 3)
+
+        If a digit's candidates in `size` rows all sit in the same `size` columns, the digit must
+        take one cell in each of those columns from these rows, so it can be removed from the rest
+        of the columns. The same holds with rows and columns swapped.
+    */
+    fn fish(&self, size: usize) -> Option<Step> {
+        let technique = if size == 2 {
+            Technique::XWing
+        } else {
+            Technique::Swordfish
+        };
+        let units = all_units();
+
+        for digit in 1..=BOARD_LENGTH as u8 {
+            for base_offset in [0, BOARD_LENGTH] {
+                let cover_offset = BOARD_LENGTH - base_offset;
+
+                // for every base line, a mask of which cover lines the digit can appear in
+                let mut bases = vec![];
+                for line in 0..BOARD_LENGTH {
+                    let mut covers = 0u16;
+                    for i in self.cells_with(&units[base_offset + line], digit) {
+                        let (row, col, _) = unit_indices(i);
+                        covers |= 1 << if base_offset == 0 { col } else { row };
+                    }
+                    if (2..=size as u32).contains(&covers.count_ones()) {
+                        bases.push((line, covers));
+                    }
+                }
+
+                for combination in combinations(bases.len(), size) {
+                    let covers = combination.iter().fold(0, |acc, &b| acc | bases[b].1);
+                    if covers.count_ones() as usize != size {
+                        continue;
+                    }
+                    let base_lines: Vec<usize> = combination.iter().map(|&b| bases[b].0).collect();
+                    let base_cells: Vec<usize> = base_lines
+                        .iter()
+                        .flat_map(|&line| self.cells_with(&units[base_offset + line], digit))
+                        .collect();
+
+                    let outside = (0..BOARD_LENGTH)
+                        .filter(|line| covers & (1 << line) != 0)
+                        .flat_map(|line| units[cover_offset + line].iter().copied())
+                        .filter(|i| !base_cells.contains(i));
+                    let eliminations = self.eliminations(outside, bit(digit));
+                    if !eliminations.is_empty() {
+                        let names: Vec<String> = base_lines
+                            .iter()
+                            .map(|&line| (line + 1).to_string())
+                            .collect();
+                        return Some(Step {
+                            technique,
+                            placements: vec![],
+                            eliminations,
+                            cells: base_cells,
+                            description: format!(
+                                "{}: {} in {} {}",
+                                technique,
+                                digit,
+                                if base_offset == 0 { "rows" } else { "columns" },
+                                names.join(", ")
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /*
+        A bivalue pivot {x, y} sees two bivalue pincers {x, z} and {y, z}. Whichever value the pivot
+        takes one of the pincers must be z, so z can be removed from any cell that sees both pincers
+    */
+    fn xy_wing(&self) -> Option<Step> {
+        let bivalue: Vec<usize> = (0..BOARD_SIZE)
+            .filter(|&i| self.candidates[i].count_ones() == 2)
+            .collect();
+
+        for &pivot in &bivalue {
+            let pivot_mask = self.candidates[pivot];
+            let pincers: Vec<usize> = bivalue
+                .iter()
+                .copied()
+                .filter(|&i| sees(pivot, i))
+                .filter(|&i| (self.candidates[i] & pivot_mask).count_ones() == 1)
+                .collect();
+
+            for (n, &a) in pincers.iter().enumerate() {
+                for &b in &pincers[n + 1..] {
+                    let z = self.candidates[a] & self.candidates[b];
+                    if z.count_ones() != 1
+                        || z & pivot_mask != 0
+                        || (self.candidates[a] | self.candidates[b]) & pivot_mask != pivot_mask
+                    {
+                        continue;
+                    }
+                    let targets = (0..BOARD_SIZE).filter(|&i| sees(a, i) && sees(b, i));
+                    let eliminations = self.eliminations(targets, z);
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::XYWing,
+                            placements: vec![],
+                            eliminations,
+                            cells: vec![pivot, a, b],
+                            description: format!(
+                                "XY-Wing: pivot {} means {} or {} is {}",
+                                cell_name(pivot),
+                                cell_name(a),
+                                cell_name(b),
+                                digit_of(z)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /*
+        A chain of bivalue cells, each seeing the next. If the first cell isn't `x` then every link is
+        forced in turn and the last cell must be `x`, so `x` can be removed from cells seeing both ends
+    */
+    fn xy_chain(&self) -> Option<Step> {
+        let bivalue: Vec<usize> = (0..BOARD_SIZE)
+            .filter(|&i| self.candidates[i].count_ones() == 2)
+            .collect();
+
+        for &start in &bivalue {
+            for target in digits_in(self.candidates[start]) {
+                let forced = self.candidates[start] & !bit(target);
+                let mut chain = vec![start];
+                if let Some(step) = self.extend_chain(&bivalue, &mut chain, forced, target) {
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+
+    fn extend_chain(
+        &self,
+        bivalue: &[usize],
+        chain: &mut Vec<usize>,
+        forced: u16,
+        target: u8,
+    ) -> Option<Step> {
+        if chain.len() >= MAX_CHAIN_LENGTH {
+            return None;
+        }
+        let last = chain[chain.len() - 1];
+
+        for &next in bivalue {
+            if chain.contains(&next) || !sees(last, next) || self.candidates[next] & forced == 0 {
+                continue;
+            }
+            let next_forced = self.candidates[next] & !forced;
+            chain.push(next);
+
+            // chains of two cells are just naked pairs, which are found earlier
+            if next_forced == bit(target) && chain.len() > 2 {
+                let start = chain[0];
+                let targets = (0..BOARD_SIZE)
+                    .filter(|i| !chain.contains(i))
+                    .filter(|&i| sees(start, i) && sees(next, i));
+                let eliminations = self.eliminations(targets, bit(target));
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::XYChain,
+                        placements: vec![],
+                        eliminations,
+                        cells: chain.clone(),
+                        description: format!(
+                            "XY-Chain: {} or {} must be {}",
+                            cell_name(start),
+                            cell_name(next),
+                            target
+                        ),
+                    });
+                }
+            }
+
+            if let Some(step) = self.extend_chain(bivalue, chain, next_forced, target) {
+                return Some(step);
+            }
+            chain.pop();
+        }
+        None
+    }
+}
+
+/*
+    Rate a puzzle by the hardest technique needed to solve it, always using the simplest step available
+*/
+pub fn grade(puzzle: &SudokuPuzzle) -> Technique {
+    let mut grid = Grid::from_puzzle(puzzle);
+    let mut hardest = Technique::NakedSingle;
+
+    while !grid.is_solved() {
+        match grid.next_step() {
+            Some(step) => {
+                hardest = hardest.max(step.technique);
+                grid.apply(&step);
+            }
+            None => return Technique::TrialAndError,
+        }
+    }
+    hardest
+}
+
+/*
+    A suggestion for the player, the cells to look at and a one line explanation
+*/
+pub struct Hint {
+    pub cells: Vec<usize>,
+    pub description: String,
+}
+
+/*
+    The techniques above only work on classic 9x9 boards, on other sizes and variants a hint
+    just looks for a cell that can only hold one digit
+*/
+pub fn single_hint(rules: Rules, grid: &[char]) -> Option<Hint> {
+    let shape = rules.shape;
+    let candidates = candidates(rules, grid);
+    (0..shape.cell_count())
+        .find(|&i| candidates[i].count_ones() == 1)
+        .map(|i| Hint {
+            cells: vec![i],
+            description: format!(
+                "Naked single: r{}c{} can only be {}",
+                i / shape.size() + 1,
+                i % shape.size() + 1,
+                shape.symbol(digit_of(candidates[i]) as usize)
+            ),
+        })
+}
+
+/*
+    Find the simplest way forward from the given board, None if no technique makes progress
+
+    Steps that only remove candidates are followed until one places a digit, since a player
+    can't act on an elimination without writing down every candidate
+*/
+pub fn hint(puzzle: &SudokuPuzzle) -> Option<Hint> {
+    let mut grid = Grid::from_puzzle(puzzle);
+    let mut cells: Vec<usize> = vec![];
+    let mut hardest: Option<Technique> = None;
+
+    while !grid.is_solved() {
+        let step = grid.next_step()?;
+        for cell in &step.cells {
+            if !cells.contains(cell) {
+                cells.push(*cell);
+            }
+        }
+
+        if !step.placements.is_empty() {
+            let description = match hardest {
+                Some(technique) => format!("{} after {}", step.description, technique),
+                None => step.description,
+            };
+            return Some(Hint { cells, description });
+        }
+
+        hardest = hardest.max(Some(step.technique));
+        grid.apply(&step);
+    }
+    None
+}
+
+/*
+    The row and column units that hold every one of the cells
+*/
+fn lines_containing(cells: &[usize]) -> Vec<usize> {
+    let (row, col, _) = unit_indices(cells[0]);
+    let mut lines = vec![];
+    if cells.iter().all(|&i| unit_indices(i).0 == row) {
+        lines.push(row);
+    }
+    if cells.iter().all(|&i| unit_indices(i).1 == col) {
+        lines.push(BOARD_LENGTH + col);
+    }
+    lines
+}
+
+fn digits_in(mask: u16) -> Vec<u8> {
+    (1..=BOARD_LENGTH as u8)
+        .filter(|d| mask & bit(*d) != 0)
+        .collect()
+}
+
+/*
+    All ways to choose `k` indices out of `0..n`, in lexicographic order
+*/
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut result = vec![];
+    let mut current = vec![];
+    fn pick(
+        start: usize,
+        n: usize,
+        k: usize,
+        current: &mut Vec<usize>,
+        result: &mut Vec<Vec<usize>>,
+    ) {
+        if current.len() == k {
+            result.push(current.clone());
+            return;
+        }
+        for i in start..n {
+            current.push(i);
+            pick(i + 1, n, k, current, result);
+            current.pop();
+        }
+    }
+    pick(0, n, k, &mut current, &mut result);
+    result
+}
+
+fn cell_name(cell: usize) -> String {
+    format!("r{}c{}", cell / BOARD_LENGTH + 1, cell % BOARD_LENGTH + 1)
+}
+
+fn unit_name(unit: usize) -> String {
+    match unit / BOARD_LENGTH {
+        0 => format!("row {}", unit + 1),
+        1 => format!("column {}", unit - BOARD_LENGTH + 1),
+        _ => format!("box {}", unit - 2 * BOARD_LENGTH + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate;
+    use crate::puzzle::{Difficulty, EMPTY_SPACE};
+    use crate::shape::CLASSIC;
+    use crate::solver::solve;
+    use rand::{thread_rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    /*
+        Walk through every step and make sure no deduction ever contradicts the real solution
+    */
+    fn assert_steps_are_sound(puzzle: &SudokuPuzzle) {
+        let solution = solve(Rules::plain(CLASSIC), puzzle).unwrap();
+        let mut grid = Grid::from_puzzle(puzzle);
+
+        while let Some(step) = grid.next_step() {
+            for &(cell, digit) in &step.placements {
+                assert_eq!(
+                    solution[cell],
+                    (b'0' + digit) as char,
+                    "{}",
+                    step.description
+                );
+            }
+            for &(cell, digit) in &step.eliminations {
+                assert_ne!(
+                    solution[cell],
+                    (b'0' + digit) as char,
+                    "{}",
+                    step.description
+                );
+            }
+            grid.apply(&step);
+        }
+    }
+
+    #[test]
+    fn steps_are_sound() {
+        for difficulty in Difficulty::ALL {
+            // fixed seeds so the generator never misses a difficulty and skips the check
+            for seed in 0..5 {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let (puzzle, _) = generate(&difficulty, &mut rng).unwrap();
+                assert_steps_are_sound(&puzzle);
+            }
+        }
+    }
+
+    #[test]
+    fn solved_grid_grades_as_single() {
+        let solution = solve(Rules::plain(CLASSIC), &[EMPTY_SPACE; BOARD_SIZE]).unwrap();
+        assert_eq!(grade(&solution.try_into().unwrap()), Technique::NakedSingle);
+    }
+
+    #[test]
+    fn finds_x_wing() {
+        // 1 is only possible in columns 2 and 6 in rows 1 and 5
+        let mut grid = Grid::from_puzzle(&[EMPTY_SPACE; BOARD_SIZE]);
+        for col in 0..BOARD_LENGTH {
+            if col != 1 && col != 5 {
+                grid.candidates[col] &= !bit(1);
+                grid.candidates[4 * BOARD_LENGTH + col] &= !bit(1);
+            }
+        }
+
+        let step = grid.fish(2).unwrap();
+        assert_eq!(step.technique, Technique::XWing);
+        assert_eq!(step.eliminations.len(), 14);
+        assert!(step.eliminations.iter().all(|&(cell, digit)| {
+            digit == 1 && (cell % BOARD_LENGTH == 1 || cell % BOARD_LENGTH == 5)
+        }));
+    }
+
+    #[test]
+    fn hint_places_a_digit() {
+        let (puzzle, solution) = generate(&Difficulty::Beginner, &mut thread_rng()).unwrap();
+        let hint = hint(&puzzle).unwrap();
+        assert!(!hint.cells.is_empty());
+        assert!(hint.cells.iter().any(|&cell| puzzle[cell] == EMPTY_SPACE));
+        assert!(
+            hint.description.starts_with("Naked single")
+                || hint.description.starts_with("Hidden single")
+        );
+
+        assert!(super::hint(&solution).is_none());
+    }
+
+    #[test]
+    fn names_cells_and_units() {
+        assert_eq!(cell_name(0), "r1c1");
+        assert_eq!(cell_name(80), "r9c9");
+        assert_eq!(unit_name(3), "row 4");
+        assert_eq!(unit_name(9), "column 1");
+        assert_eq!(unit_name(26), "box 9");
+    }
+}
@@ -1,11 +1,105 @@
+mod canonical;
+mod cli;
+mod daily;
 mod events;
+mod formats;
+mod generator;
+mod history;
+mod killer;
+mod logic;
+mod pack;
 mod puzzle;
 mod ui;
 use ui::UI;
 mod themes;
 mod puzzle_transformer;
+mod save;
+mod shape;
+mod solver;
+
+use clap::Parser;
+use cli::{exit_with_error, Cli, Command, PlayArgs};
+use daily::Date;
+use pack::PuzzlePack;
+use puzzle::{Difficulty, Puzzle, PuzzleId, Variant};
+use shape::CLASSIC;
 
 fn main() {
-    let mut ui = UI::new();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Play(args)) => play(args),
+        Some(Command::Daily { difficulty, date }) => {
+            daily(date.unwrap_or_else(Date::today), difficulty)
+        }
+        Some(Command::Solve { grids, file }) => cli::solve(&grids, &file),
+        Some(Command::Generate {
+            count,
+            difficulty,
+            format,
+            output,
+            seed,
+        }) => cli::generate(count, difficulty, format, output.as_deref(), seed),
+        Some(Command::Dedupe { grids, file }) => cli::dedupe(&grids, &file),
+        Some(Command::Grade { grid }) => cli::grade_puzzle(&grid),
+        None => play(cli.play),
+    }
+}
+
+fn play(args: PlayArgs) {
+    let ui = match (
+        args.file,
+        args.seed,
+        args.size,
+        args.variant,
+        args.difficulty,
+    ) {
+        // a file with more than one puzzle is played through as a pack
+        (Some(path), _, _, _, _) => match PuzzlePack::load(&path) {
+            Ok(pack) if pack.puzzle_count() > 1 => UI::with_pack(pack),
+            Ok(pack) => UI::with_puzzle(pack.current().clone()),
+            Err(err) => exit_with_error(&err.to_string()),
+        },
+        (None, Some(seed), _, _, difficulty) => {
+            match PuzzleId::parse(&seed, difficulty.unwrap_or(Difficulty::Beginner)) {
+                Ok(id) => UI::with_puzzle(Puzzle::from_id(id)),
+                Err(err) => exit_with_error(&err),
+            }
+        }
+        (None, None, None, None, Some(difficulty)) => UI::new(difficulty),
+        // without an explicit difficulty, size or variant, pick up the last game if there is one
+        (None, None, None, None, None) => match save::load() {
+            Some(game) => UI::from_save(game),
+            None => UI::new(Difficulty::Beginner),
+        },
+        (None, None, size, variant, difficulty) => {
+            let id = PuzzleId::random(
+                size.unwrap_or(CLASSIC),
+                variant.unwrap_or(Variant::Classic),
+                difficulty.unwrap_or(Difficulty::Beginner),
+            );
+            UI::with_puzzle(Puzzle::from_id(id))
+        }
+    };
+    run(ui);
+}
+
+fn daily(date: Date, difficulty: Difficulty) {
+    let id = date.puzzle_id(difficulty);
+    // carry on with today's puzzle if that's what was being played last
+    let ui = match save::load() {
+        Some(game) if game.daily == Some(date) && game.puzzle.id == Some(id) => {
+            UI::from_save(game)
+        }
+        _ => UI::daily(date, difficulty),
+    };
+    run(ui);
+}
+
+fn run(mut ui: UI) {
     ui.run();
+
+    if let Err(err) = save::save(&ui.to_save()) {
+        eprintln!("Could not save the game: {}", err);
+    }
 }
@@ -0,0 +1,191 @@
+use crate::formats::{puzzle_from_grid, read_collection, ImportError};
+use crate::puzzle::Puzzle;
+use std::fs;
+
+/*
+    How far the player got through a pack, enough to load it again later
+*/
+#[derive(Clone)]
+pub struct PackProgress {
+    pub path: String,
+    pub index: usize,
+    pub solved: Vec<usize>,
+}
+
+/*
+    A collection of puzzles loaded from a file, played through in order
+*/
+pub struct PuzzlePack {
+    path: String,
+    puzzles: Vec<Puzzle>,
+    solved: Vec<bool>,
+    index: usize,
+}
+
+impl PuzzlePack {
+    /*
+        Load every puzzle in the file, each one must have exactly one solution
+    */
+    pub fn load(path: &str) -> Result<PuzzlePack, ImportError> {
+        let puzzles = read_collection(path)?
+            .into_iter()
+            .enumerate()
+            .map(|(n, grid)| {
+                puzzle_from_grid(grid).map_err(|err| ImportError::InPuzzle(n + 1, Box::new(err)))
+            })
+            .collect::<Result<Vec<Puzzle>, ImportError>>()?;
+
+        // the full path is saved, so the pack can be found again from any directory
+        let path = match path {
+            "-" => path.to_string(),
+            _ => fs::canonicalize(path)
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_else(|_| path.to_string()),
+        };
+        Ok(PuzzlePack::from_puzzles(&path, puzzles))
+    }
+
+    /*
+        Load the pack again and pick up where the player left off
+    */
+    pub fn resume(progress: &PackProgress) -> Result<PuzzlePack, ImportError> {
+        let mut pack = PuzzlePack::load(&progress.path)?;
+        pack.index = progress.index.min(pack.puzzle_count() - 1);
+        for &i in &progress.solved {
+            if i < pack.puzzle_count() {
+                pack.solved[i] = true;
+            }
+        }
+        Ok(pack)
+    }
+
+    fn from_puzzles(path: &str, puzzles: Vec<Puzzle>) -> PuzzlePack {
+        PuzzlePack {
+            path: path.to_string(),
+            solved: vec![false; puzzles.len()],
+            puzzles,
+            index: 0,
+        }
+    }
+
+    /*
+        Progress can only be saved if the pack can be read again, so not for stdin
+    */
+    pub fn progress(&self) -> Option<PackProgress> {
+        if self.path == "-" {
+            return None;
+        }
+        Some(PackProgress {
+            path: self.path.clone(),
+            index: self.index,
+            solved: (0..self.puzzle_count())
+                .filter(|&i| self.solved[i])
+                .collect(),
+        })
+    }
+
+    pub fn current(&self) -> &Puzzle {
+        &self.puzzles[self.index]
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn puzzle_count(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn solved_count(&self) -> usize {
+        self.solved.iter().filter(|solved| **solved).count()
+    }
+
+    pub fn is_current_solved(&self) -> bool {
+        self.solved[self.index]
+    }
+
+    pub fn mark_solved(&mut self) {
+        self.solved[self.index] = true;
+    }
+
+    /*
+        Move to the next puzzle, returns false if already on the last one
+    */
+    pub fn next(&mut self) -> bool {
+        if self.index + 1 < self.puzzle_count() {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /*
+        Move to the previous puzzle, returns false if already on the first one
+    */
+    pub fn previous(&mut self) -> bool {
+        if self.index > 0 {
+            self.index -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Difficulty;
+
+    fn pack_of(size: usize) -> PuzzlePack {
+        let puzzles = (0..size)
+            .map(|_| Puzzle::new_puzzle(Difficulty::Beginner))
+            .collect();
+        PuzzlePack::from_puzzles("pack.sdm", puzzles)
+    }
+
+    #[test]
+    fn step_through_pack() {
+        let mut pack = pack_of(3);
+        assert!(!pack.previous());
+        assert!(pack.next());
+        assert!(pack.next());
+        assert!(!pack.next());
+        assert_eq!(pack.index(), 2);
+        assert!(pack.previous());
+        assert_eq!(pack.current().puzzle, pack.puzzles[1].puzzle);
+    }
+
+    #[test]
+    fn track_solved_puzzles() {
+        let mut pack = pack_of(3);
+        pack.next();
+        pack.mark_solved();
+        pack.mark_solved();
+
+        assert!(pack.is_current_solved());
+        assert_eq!(pack.solved_count(), 1);
+
+        let progress = pack.progress().unwrap();
+        assert_eq!(progress.index, 1);
+        assert_eq!(progress.solved, vec![1]);
+    }
+
+    #[test]
+    fn progress_keeps_full_path() {
+        let dir = std::env::temp_dir().join("sudoku-rs-pack-test");
+        fs::create_dir_all(&dir).unwrap();
+        let line =
+            "..27.4..6......5....1.6...35.....9...4.....15.13...6..374...2....6.2549.........1";
+        fs::write(dir.join("pack.sdm"), format!("{}\n{}\n", line, line)).unwrap();
+
+        // a roundabout path still saves the real location of the file
+        let roundabout = dir.join("..").join("sudoku-rs-pack-test").join("pack.sdm");
+        let pack = PuzzlePack::load(roundabout.to_str().unwrap()).unwrap();
+        let progress = pack.progress().unwrap();
+        let full = fs::canonicalize(dir.join("pack.sdm")).unwrap();
+        assert_eq!(progress.path, full.to_str().unwrap());
+        assert_eq!(PuzzlePack::resume(&progress).unwrap().puzzle_count(), 2);
+    }
+}
@@ -1,29 +1,211 @@
 use crate::puzzle::SudokuPuzzle;
 use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
 
 /*
-    Apply a series of transformations to the puzzle that keeps the puzzle solveable
+    A series of changes to a puzzle that keep it solveable, in the order they are applied
 
-    This means we can use one puzzle "seed" and get plenty of puzzles out of it
-
-    Note: All transforms performed on the puzzle must also be done to the solution
+    Applying the same transform to the same seed always gives the same puzzle, so this is
+    enough to trace a puzzle back to its seed or to make it again
 */
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Transform {
+    // every digit d is replaced by digits[d - 1]
+    pub digits: [char; 9],
+    pub reflect_horizontal: bool,
+    pub reflect_vertical: bool,
+    // how many times the puzzle is rotated by 90 degrees
+    pub rotations: u8,
+    // band i is moved from band bands[i], and row j of band i from row rows[i][j] of that band
+    pub bands: [usize; 3],
+    pub rows: [[usize; 3]; 3],
+    // the same for stacks and the columns inside them
+    pub stacks: [usize; 3],
+    pub columns: [[usize; 3]; 3],
+    pub transpose: bool,
+}
+
+impl Transform {
+    /*
+        Pick a random series of transformations that keeps the puzzle solveable
 
-pub fn transform_puzzle(mut game: (&mut SudokuPuzzle, &mut SudokuPuzzle)) {
-    randomize_numbers(&mut game);
-    flop(&mut game);
-    rotate(&mut game);
+        This means we can use one puzzle "seed" and get plenty of puzzles out of it
+
+        Every random choice is taken from `rng`, so the same seed always gives the same puzzle
+    */
+    pub fn random<R: Rng>(rng: &mut R) -> Transform {
+        let mut digits = ['1', '2', '3', '4', '5', '6', '7', '8', '9'];
+        digits.shuffle(rng);
+        let reflect_horizontal = rng.gen_bool(0.5);
+        let reflect_vertical = rng.gen_bool(0.5);
+        let rotations = rng.gen_range(0..=3);
+        let bands = random_order(rng);
+        let rows = [random_order(rng), random_order(rng), random_order(rng)];
+        let stacks = random_order(rng);
+        let columns = [random_order(rng), random_order(rng), random_order(rng)];
+
+        Transform {
+            digits,
+            reflect_horizontal,
+            reflect_vertical,
+            rotations,
+            bands,
+            rows,
+            stacks,
+            columns,
+            transpose: rng.gen_bool(0.5),
+        }
+    }
+
+    /*
+        Note: All transforms performed on the puzzle must also be done to the solution
+    */
+    pub fn apply(&self, mut game: (&mut SudokuPuzzle, &mut SudokuPuzzle)) {
+        randomize_numbers(&mut game, &self.digits);
+        if self.reflect_horizontal {
+            reflect_horizontal(&mut game);
+        }
+        if self.reflect_vertical {
+            reflect_vertical(&mut game);
+        }
+        rotate(&mut game, self.rotations);
+        swap_bands(&mut game, &self.bands);
+        swap_rows_in_bands(&mut game, &self.rows);
+        swap_stacks(&mut game, &self.stacks);
+        swap_columns_in_stacks(&mut game, &self.columns);
+        if self.transpose {
+            transpose(game.0);
+            transpose(game.1);
+        }
+    }
 }
 
 /*
-    Create a bijection from [1,9] -> [1,9] and map all numbers to create a new puzzle
+    Written as `digits=915372846 reflect=hv rotate=2 bands=201 rows=012,210,102 stacks=120
+    columns=021,012,210 transpose=yes`
 */
-fn randomize_numbers((puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuzzle)) {
-    let mut rng = thread_rng();
-    let mut mapping = ['1', '2', '3', '4', '5', '6', '7', '8', '9'];
-    mapping.shuffle(&mut rng);
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reflect = match (self.reflect_horizontal, self.reflect_vertical) {
+            (false, false) => "none",
+            (true, false) => "h",
+            (false, true) => "v",
+            (true, true) => "hv",
+        };
+        write!(
+            f,
+            "digits={} reflect={} rotate={} bands={} rows={} stacks={} columns={} transpose={}",
+            self.digits.iter().collect::<String>(),
+            reflect,
+            self.rotations,
+            order_string(&self.bands),
+            self.rows
+                .iter()
+                .map(order_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            order_string(&self.stacks),
+            self.columns
+                .iter()
+                .map(order_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            if self.transpose { "yes" } else { "no" }
+        )
+    }
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid transform '{}'", s);
+        let value = |key: &str| {
+            s.split_whitespace()
+                .find_map(|part| part.strip_prefix(key)?.strip_prefix('='))
+                .ok_or_else(error)
+        };
+        let orders = |text: &str| -> Result<[[usize; 3]; 3], String> {
+            let orders = text
+                .split(',')
+                .map(parse_order)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(error)?;
+            orders.try_into().map_err(|_| error())
+        };
+
+        let digits: [char; 9] = value("digits")?
+            .chars()
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| error())?;
+        let mut sorted = digits;
+        sorted.sort();
+        if sorted != ['1', '2', '3', '4', '5', '6', '7', '8', '9'] {
+            return Err(error());
+        }
+        let (reflect_horizontal, reflect_vertical) = match value("reflect")? {
+            "none" => (false, false),
+            "h" => (true, false),
+            "v" => (false, true),
+            "hv" => (true, true),
+            _ => return Err(error()),
+        };
 
+        Ok(Transform {
+            digits,
+            reflect_horizontal,
+            reflect_vertical,
+            rotations: value("rotate")?
+                .parse()
+                .ok()
+                .filter(|r| *r <= 3)
+                .ok_or_else(error)?,
+            bands: parse_order(value("bands")?).ok_or_else(error)?,
+            rows: orders(value("rows")?)?,
+            stacks: parse_order(value("stacks")?).ok_or_else(error)?,
+            columns: orders(value("columns")?)?,
+            transpose: match value("transpose")? {
+                "yes" => true,
+                "no" => false,
+                _ => return Err(error()),
+            },
+        })
+    }
+}
+
+fn random_order<R: Rng>(rng: &mut R) -> [usize; 3] {
+    let mut order = [0, 1, 2];
+    order.shuffle(rng);
+    order
+}
+
+fn order_string(order: &[usize; 3]) -> String {
+    order.iter().map(|i| i.to_string()).collect()
+}
+
+fn parse_order(text: &str) -> Option<[usize; 3]> {
+    let order: Vec<usize> = text
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as usize))
+        .collect::<Option<_>>()?;
+    let mut sorted = order.clone();
+    sorted.sort();
+    if sorted != [0, 1, 2] {
+        return None;
+    }
+    order.try_into().ok()
+}
+
+/*
+    Map all numbers through a bijection from [1,9] -> [1,9] to create a new puzzle
+*/
+fn randomize_numbers(
+    (puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuzzle),
+    mapping: &[char; 9],
+) {
     for i in 0..81 {
         solution[i] = match solution[i] {
             '1' => mapping[0],
@@ -48,9 +230,8 @@ fn randomize_numbers((puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuz
 /*
     Rotate a puzzle, either 0, 90, 180, or 270 degrees
 */
-fn rotate((puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuzzle)) {
-    let rot_count = rand::thread_rng().gen_range(0..=3);
-    for _ in 0..rot_count {
+fn rotate((puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuzzle), rotations: u8) {
+    for _ in 0..rotations {
         rotate_90(puzzle);
         rotate_90(solution);
     }
@@ -60,7 +241,7 @@ fn rotate((puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuzzle)) {
     Rotate the given puzzle 90 degrees
 */
 fn rotate_90(matrix: &mut SudokuPuzzle) {
-    let mut res = matrix.clone();
+    let mut res = *matrix;
 
     for i in 0..9 {
         for j in 0..9 {
@@ -73,19 +254,6 @@ fn rotate_90(matrix: &mut SudokuPuzzle) {
     matrix[..81].clone_from_slice(&res[..81]);
 }
 
-/*
-    Reflect the puzzle either vertically or horizontally with a 50 percent probability 
-*/
-fn flop(game: &mut (&mut SudokuPuzzle, &mut SudokuPuzzle)) {
-    if rand::thread_rng().gen_bool(0.5) {
-        reflect_horizontal(game);
-    }
-
-    if rand::thread_rng().gen_bool(0.5) {
-        reflect_vertical(game);
-    }
-}
-
 /*
     Reflect the puzzle horizontally across the center row
 */
@@ -118,3 +286,128 @@ fn reflect_vertical(game: &mut (&mut SudokuPuzzle, &mut SudokuPuzzle)) {
     rotate_90(game.1);
     reflect_horizontal(game);
 }
+
+/*
+    Shuffle the three bands, the groups of three rows that share boxes
+*/
+fn swap_bands((puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuzzle), bands: &[usize; 3]) {
+    let mut order = [0; 9];
+    for (band, from) in bands.iter().enumerate() {
+        for row in 0..3 {
+            order[band * 3 + row] = from * 3 + row;
+        }
+    }
+    reorder_rows(puzzle, &order);
+    reorder_rows(solution, &order);
+}
+
+/*
+    Shuffle the rows inside each band, rows can't leave their band or boxes would break
+*/
+fn swap_rows_in_bands(
+    (puzzle, solution): &mut (&mut SudokuPuzzle, &mut SudokuPuzzle),
+    rows: &[[usize; 3]; 3],
+) {
+    let mut order = [0; 9];
+    for band in 0..3 {
+        for row in 0..3 {
+            order[band * 3 + row] = band * 3 + rows[band][row];
+        }
+    }
+    reorder_rows(puzzle, &order);
+    reorder_rows(solution, &order);
+}
+
+/*
+    Stacks are the columns version of bands, so transpose, shuffle the bands and transpose back
+*/
+fn swap_stacks(game: &mut (&mut SudokuPuzzle, &mut SudokuPuzzle), stacks: &[usize; 3]) {
+    transpose(game.0);
+    transpose(game.1);
+    swap_bands(game, stacks);
+    transpose(game.0);
+    transpose(game.1);
+}
+
+fn swap_columns_in_stacks(
+    game: &mut (&mut SudokuPuzzle, &mut SudokuPuzzle),
+    columns: &[[usize; 3]; 3],
+) {
+    transpose(game.0);
+    transpose(game.1);
+    swap_rows_in_bands(game, columns);
+    transpose(game.0);
+    transpose(game.1);
+}
+
+/*
+    Move row `order[i]` to row `i`
+*/
+fn reorder_rows(matrix: &mut SudokuPuzzle, order: &[usize; 9]) {
+    let res = *matrix;
+    for (row, from) in order.iter().enumerate() {
+        matrix[row * 9..row * 9 + 9].copy_from_slice(&res[from * 9..from * 9 + 9]);
+    }
+}
+
+/*
+    Reflect the puzzle across the main diagonal, rows become columns
+*/
+fn transpose(matrix: &mut SudokuPuzzle) {
+    let res = *matrix;
+    for i in 0..9 {
+        for j in 0..9 {
+            matrix[j * 9 + i] = res[i * 9 + j];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    // every cell holds the number of its row, or of its column when `by_column` is set
+    fn lines(by_column: bool) -> SudokuPuzzle {
+        let mut grid = ['_'; 81];
+        for (i, cell) in grid.iter_mut().enumerate() {
+            let line = if by_column { i % 9 } else { i / 9 };
+            *cell = char::from_digit(line as u32 + 1, 10).unwrap();
+        }
+        grid
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let mut grid = lines(false);
+        transpose(&mut grid);
+        assert_eq!(grid, lines(true));
+        transpose(&mut grid);
+        assert_eq!(grid, lines(false));
+    }
+
+    #[test]
+    fn lines_stay_in_their_band() {
+        let (mut puzzle, mut solution) = (lines(false), lines(true));
+        let mut game = (&mut puzzle, &mut solution);
+        let transform = Transform::random(&mut thread_rng());
+        swap_rows_in_bands(&mut game, &transform.rows);
+        swap_columns_in_stacks(&mut game, &transform.columns);
+
+        for i in 0..9 {
+            let row = puzzle[i * 9].to_digit(10).unwrap() as usize - 1;
+            let col = solution[i].to_digit(10).unwrap() as usize - 1;
+            assert_eq!(row / 3, i / 3);
+            assert_eq!(col / 3, i / 3);
+        }
+    }
+
+    #[test]
+    fn transform_round_trip() {
+        for _ in 0..10 {
+            let transform = Transform::random(&mut thread_rng());
+            assert_eq!(transform.to_string().parse(), Ok(transform));
+        }
+        assert!("digits=123 reflect=none".parse::<Transform>().is_err());
+    }
+}
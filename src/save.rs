@@ -0,0 +1,305 @@
+use crate::canonical::canonical_form;
+use crate::daily::Date;
+use crate::killer::Cage;
+use crate::pack::PackProgress;
+use crate::puzzle::{Difficulty, Puzzle, PuzzleId, SudokuPuzzle, Variant, EMPTY_SPACE};
+use crate::shape::Shape;
+use std::io::Write;
+use std::{env, fs, io, path::PathBuf};
+
+const SAVE_FILE: &str = "save";
+const PLAYED_FILE: &str = "played";
+
+/*
+    Everything needed to pick a game back up where the player left off
+*/
+pub struct SavedGame {
+    pub puzzle: Puzzle,
+    pub displayed_puzzle: Vec<char>,
+    pub notes: Vec<u16>,
+    pub time_in_ms: u64,
+    pub gave_up: bool,
+    pub has_won: bool,
+    pub pack: Option<PackProgress>,
+    pub daily: Option<Date>,
+}
+
+/*
+    The directory we keep our files in, following the XDG base directory spec
+    https://specifications.freedesktop.org/basedir-spec/latest/
+*/
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+    };
+    Some(base.join("sudoku-rs"))
+}
+
+fn create_data_dir() -> io::Result<PathBuf> {
+    let dir = data_dir().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "could not find a data directory")
+    })?;
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+pub fn save(game: &SavedGame) -> io::Result<()> {
+    fs::write(create_data_dir()?.join(SAVE_FILE), to_save_string(game))
+}
+
+/*
+    Remember that a puzzle has been played, returns true if it had been played before
+
+    Puzzles are kept in their canonical form, one per line, so a puzzle that comes back
+    relabeled, rotated or shuffled is still recognised
+*/
+pub fn record_played(puzzle: &SudokuPuzzle) -> io::Result<bool> {
+    let path = create_data_dir()?.join(PLAYED_FILE);
+    let line: String = canonical_form(puzzle).iter().collect();
+
+    let played = fs::read_to_string(&path).unwrap_or_default();
+    if played.lines().any(|played| played == line) {
+        return Ok(true);
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(false)
+}
+
+/*
+    Load the last saved game, None if there isn't one or it can't be read
+*/
+pub fn load() -> Option<SavedGame> {
+    let contents = fs::read_to_string(data_dir()?.join(SAVE_FILE)).ok()?;
+    parse_save_string(&contents)
+}
+
+/*
+    Games are saved as one `key=value` pair per line
+*/
+fn to_save_string(game: &SavedGame) -> String {
+    let notes: Vec<String> = game.notes.iter().map(|n| n.to_string()).collect();
+    let mut text = format!(
+        "puzzle={}\nsolution={}\ndisplayed={}\nnotes={}\ndifficulty={}\ntime_in_ms={}\ngave_up={}\nhas_won={}\n",
+        game.puzzle.puzzle.iter().collect::<String>(),
+        game.puzzle.solution.iter().collect::<String>(),
+        game.displayed_puzzle.iter().collect::<String>(),
+        notes.join(","),
+        game.puzzle.difficulty,
+        game.time_in_ms,
+        game.gave_up,
+        game.has_won,
+    );
+
+    if let Some(id) = game.puzzle.id {
+        text.push_str(&format!("id={}\n", id));
+    }
+    if let Some(origin) = game.puzzle.origin {
+        text.push_str(&format!("origin={}\n", origin));
+    }
+    if game.puzzle.variant != Variant::Classic {
+        text.push_str(&format!("variant={}\n", game.puzzle.variant));
+    }
+    if !game.puzzle.cages.is_empty() {
+        let cages: Vec<String> = game.puzzle.cages.iter().map(|c| c.to_string()).collect();
+        text.push_str(&format!("cages={}\n", cages.join(";")));
+    }
+    if let Some(date) = game.daily {
+        text.push_str(&format!("daily={}\n", date));
+    }
+    if let Some(pack) = &game.pack {
+        let solved: Vec<String> = pack.solved.iter().map(|i| i.to_string()).collect();
+        text.push_str(&format!(
+            "pack={}\npack_index={}\npack_solved={}\n",
+            pack.path,
+            pack.index,
+            solved.join(",")
+        ));
+    }
+    text
+}
+
+fn parse_save_string(contents: &str) -> Option<SavedGame> {
+    let value = |key: &str| {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+    };
+
+    // the size of the board is told by how many cells it has
+    let puzzle: Vec<char> = value("puzzle")?.chars().collect();
+    let shape = Shape::from_cell_count(puzzle.len())?;
+    // a cell holds one of the board's digits or is empty, anything else means the save is corrupt
+    let is_cell =
+        |c: &char| *c == EMPTY_SPACE || (!c.is_ascii_lowercase() && shape.digit_of(*c).is_some());
+    if !puzzle.iter().all(is_cell) {
+        return None;
+    }
+    let parse_grid = |key: &str| {
+        let grid: Vec<char> = value(key)?.chars().collect();
+        (grid.len() == shape.cell_count() && grid.iter().all(is_cell)).then_some(grid)
+    };
+
+    let notes: Vec<u16> = value("notes")?
+        .split(',')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    if notes.len() != shape.cell_count() {
+        return None;
+    }
+
+    // packs are optional, older saves and games outside a pack won't have them
+    let pack = value("pack").and_then(|path| {
+        Some(PackProgress {
+            path: path.to_string(),
+            index: value("pack_index")?.parse().ok()?,
+            solved: value("pack_solved")?
+                .split(',')
+                .filter(|i| !i.is_empty())
+                .map(|i| i.parse().ok())
+                .collect::<Option<_>>()?,
+        })
+    });
+
+    // saves from before variants were added are all classic
+    let variant = match value("variant") {
+        Some(variant) => variant.parse::<Variant>().ok()?,
+        None => Variant::Classic,
+    };
+    let cages: Vec<Cage> = value("cages")
+        .unwrap_or_default()
+        .split(';')
+        .filter(|cage| !cage.is_empty())
+        .map(|cage| cage.parse().ok())
+        .collect::<Option<_>>()?;
+    if cages
+        .iter()
+        .flat_map(|cage| &cage.cells)
+        .any(|&cell| cell >= shape.cell_count())
+    {
+        return None;
+    }
+
+    let difficulty = value("difficulty")?.parse::<Difficulty>().ok()?;
+    Some(SavedGame {
+        puzzle: Puzzle {
+            shape,
+            puzzle,
+            solution: parse_grid("solution")?,
+            variant,
+            cages,
+            difficulty,
+            id: value("id").and_then(|id| PuzzleId::parse(id, difficulty).ok()),
+            origin: value("origin").and_then(|origin| origin.parse().ok()),
+        },
+        displayed_puzzle: parse_grid("displayed")?,
+        notes,
+        time_in_ms: value("time_in_ms")?.parse().ok()?,
+        gave_up: value("gave_up")?.parse().ok()?,
+        has_won: value("has_won")?.parse().ok()?,
+        pack,
+        daily: value("daily").and_then(|date| date.parse().ok()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_round_trip() {
+        let puzzle = Puzzle::new_puzzle(Difficulty::Beginner);
+        let mut displayed_puzzle = puzzle.puzzle.clone();
+        let mut notes = vec![0; puzzle.shape.cell_count()];
+        let empty = displayed_puzzle.iter().position(|c| *c == EMPTY_SPACE).unwrap();
+        displayed_puzzle[empty] = puzzle.solution[empty];
+        notes[empty + 1] = 0b101;
+
+        let game = SavedGame {
+            puzzle,
+            displayed_puzzle,
+            notes,
+            time_in_ms: 12_250,
+            gave_up: false,
+            has_won: true,
+            pack: Some(PackProgress {
+                path: "/tmp/pack.sdm".to_string(),
+                index: 4,
+                solved: vec![0, 2, 3],
+            }),
+            daily: "2026-10-18".parse().ok(),
+        };
+        let loaded = parse_save_string(&to_save_string(&game)).unwrap();
+
+        assert_eq!(loaded.puzzle.puzzle, game.puzzle.puzzle);
+        assert_eq!(loaded.puzzle.solution, game.puzzle.solution);
+        assert!(loaded.puzzle.difficulty == game.puzzle.difficulty);
+        assert_eq!(loaded.puzzle.id, game.puzzle.id);
+        assert_eq!(loaded.displayed_puzzle, game.displayed_puzzle);
+        assert_eq!(loaded.notes, game.notes);
+        assert_eq!(loaded.time_in_ms, game.time_in_ms);
+        assert_eq!(loaded.gave_up, game.gave_up);
+        assert_eq!(loaded.has_won, game.has_won);
+        assert_eq!(loaded.daily, game.daily);
+
+        let pack = loaded.pack.unwrap();
+        assert_eq!(pack.path, "/tmp/pack.sdm");
+        assert_eq!(pack.index, 4);
+        assert_eq!(pack.solved, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn small_board_round_trip() {
+        let shape = "6x6".parse().unwrap();
+        let puzzle = Puzzle::from_id(PuzzleId::random(shape, Variant::Killer, Difficulty::Easy));
+        let game = SavedGame {
+            displayed_puzzle: puzzle.puzzle.clone(),
+            notes: vec![0; shape.cell_count()],
+            puzzle,
+            time_in_ms: 0,
+            gave_up: false,
+            has_won: false,
+            pack: None,
+            daily: None,
+        };
+        let loaded = parse_save_string(&to_save_string(&game)).unwrap();
+
+        assert_eq!(loaded.puzzle.shape, shape);
+        assert_eq!(loaded.puzzle.variant, Variant::Killer);
+        assert_eq!(loaded.puzzle.cages, game.puzzle.cages);
+        assert_eq!(loaded.puzzle.solution, game.puzzle.solution);
+        assert_eq!(loaded.puzzle.id, game.puzzle.id);
+    }
+
+    #[test]
+    fn corrupt_save_is_ignored() {
+        assert!(parse_save_string("").is_none());
+        assert!(parse_save_string("puzzle=123\nsolution=456").is_none());
+
+        let puzzle = Puzzle::new_puzzle(Difficulty::Beginner);
+        let game = SavedGame {
+            displayed_puzzle: puzzle.puzzle.clone(),
+            notes: vec![0; puzzle.shape.cell_count()],
+            puzzle,
+            time_in_ms: 0,
+            gave_up: false,
+            has_won: false,
+            pack: None,
+            daily: None,
+        };
+        let contents = to_save_string(&game);
+        assert!(parse_save_string(&contents).is_some());
+        for bad in ['0', 'A', '?'] {
+            let displayed = format!("displayed={}", bad.to_string().repeat(81));
+            let corrupt: Vec<String> = contents
+                .lines()
+                .map(|line| match line.starts_with("displayed=") {
+                    true => displayed.clone(),
+                    false => line.to_string(),
+                })
+                .collect();
+            assert!(parse_save_string(&corrupt.join("\n")).is_none());
+        }
+    }
+}
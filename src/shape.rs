@@ -0,0 +1,210 @@
+use std::fmt;
+use std::str::FromStr;
+
+// the digits used on a board, boards bigger than 9x9 carry on with letters
+const SYMBOLS: [char; 16] = [
+    '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G',
+];
+
+/*
+    The size of a board, given by the size of its boxes
+
+    A board is as wide and tall as a box has cells, so 2x3 boxes make a 6x6 board
+    with two boxes across and three down
+*/
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct Shape {
+    pub box_rows: usize,
+    pub box_cols: usize,
+}
+
+pub const CLASSIC: Shape = Shape {
+    box_rows: 3,
+    box_cols: 3,
+};
+
+pub const SHAPES: [Shape; 5] = [
+    Shape {
+        box_rows: 2,
+        box_cols: 2,
+    },
+    Shape {
+        box_rows: 2,
+        box_cols: 3,
+    },
+    CLASSIC,
+    Shape {
+        box_rows: 3,
+        box_cols: 4,
+    },
+    Shape {
+        box_rows: 4,
+        box_cols: 4,
+    },
+];
+
+impl Shape {
+    /*
+        How many cells there are in a row, column or box, and so how many digits are used
+    */
+    pub fn size(&self) -> usize {
+        self.box_rows * self.box_cols
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.size() * self.size()
+    }
+
+    /*
+        Find the shape of a board from how many cells it has
+    */
+    pub fn from_cell_count(cells: usize) -> Option<Shape> {
+        SHAPES.into_iter().find(|shape| shape.cell_count() == cells)
+    }
+
+    /*
+        The symbol for a digit from 1 to `size`
+    */
+    pub fn symbol(&self, digit: usize) -> char {
+        SYMBOLS[digit - 1]
+    }
+
+    /*
+        The digit a symbol stands for, None if it isn't used on this board
+    */
+    pub fn digit_of(&self, symbol: char) -> Option<usize> {
+        let symbol = symbol.to_ascii_uppercase();
+        SYMBOLS[..self.size()]
+            .iter()
+            .position(|s| *s == symbol)
+            .map(|i| i + 1)
+    }
+
+    /*
+        Returns the (row, column, box) that a board index belongs to
+    */
+    pub fn unit_indices(&self, index: usize) -> (usize, usize, usize) {
+        let row = index / self.size();
+        let col = index % self.size();
+        let square = (row / self.box_rows) * self.box_rows + col / self.box_cols;
+        (row, col, square)
+    }
+
+    /*
+        The cells of unit `unit`, numbered like `all_units` in the solver: rows first,
+        then columns, then boxes
+    */
+    pub fn unit(&self, unit: usize) -> Vec<usize> {
+        let size = self.size();
+        let n = unit % size;
+        (0..size)
+            .map(|i| match unit / size {
+                0 => n * size + i,
+                1 => i * size + n,
+                _ => {
+                    let row = (n / self.box_rows) * self.box_rows + i / self.box_cols;
+                    let col = (n % self.box_rows) * self.box_cols + i % self.box_cols;
+                    row * size + col
+                }
+            })
+            .collect()
+    }
+
+    /*
+        Whether a cell lies on the diagonal running down from the top left corner, and on the
+        one running down from the top right corner
+    */
+    pub fn diagonals(&self, index: usize) -> (bool, bool) {
+        let (row, col) = (index / self.size(), index % self.size());
+        (row == col, row + col == self.size() - 1)
+    }
+
+    /*
+        The cells of both main diagonals, top left to bottom right and then top right to bottom left
+    */
+    pub fn diagonal_units(&self) -> [Vec<usize>; 2] {
+        let size = self.size();
+        [
+            (0..size).map(|i| i * size + i).collect(),
+            (0..size).map(|i| i * size + size - 1 - i).collect(),
+        ]
+    }
+
+    pub fn unit_count(&self) -> usize {
+        self.size() * 3
+    }
+
+    /*
+        Two different cells see each other if they share a row, column or box
+    */
+    pub fn sees(&self, a: usize, b: usize) -> bool {
+        let (row_a, col_a, box_a) = self.unit_indices(a);
+        let (row_b, col_b, box_b) = self.unit_indices(b);
+        a != b && (row_a == row_b || col_a == col_b || box_a == box_b)
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.size(), self.size())
+    }
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    /*
+        Parse a board size such as `6x6` or just `6`
+    */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let side = match s.trim().split_once('x') {
+            Some((side, other)) if side == other => side,
+            Some(_) => "",
+            None => s.trim(),
+        };
+        SHAPES
+            .into_iter()
+            .find(|shape| side.parse() == Ok(shape.size()))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown board size '{}', try 4x4, 6x6, 9x9, 12x12 or 16x16",
+                    s
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_shapes() {
+        assert_eq!("6x6".parse(), Ok(SHAPES[1]));
+        assert_eq!("16".parse(), Ok(SHAPES[4]));
+        assert!("5x5".parse::<Shape>().is_err());
+        assert!("6x9".parse::<Shape>().is_err());
+    }
+
+    #[test]
+    fn boxes_of_a_six_by_six() {
+        let shape = SHAPES[1];
+        // boxes are two rows tall and three columns wide
+        assert_eq!(shape.unit(12), vec![0, 1, 2, 6, 7, 8]);
+        assert_eq!(shape.unit(13), vec![3, 4, 5, 9, 10, 11]);
+        assert_eq!(shape.unit(14), vec![12, 13, 14, 18, 19, 20]);
+        for unit in 12..18 {
+            for cell in shape.unit(unit) {
+                assert_eq!(shape.unit_indices(cell).2, unit - 12);
+            }
+        }
+    }
+
+    #[test]
+    fn symbols() {
+        let hex = SHAPES[4];
+        assert_eq!(hex.symbol(16), 'G');
+        assert_eq!(hex.digit_of('a'), Some(10));
+        assert_eq!(SHAPES[0].digit_of('5'), None);
+    }
+}
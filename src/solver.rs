@@ -0,0 +1,627 @@
+use crate::killer::{cage_index, Cage};
+use crate::puzzle::{SudokuPuzzle, EMPTY_SPACE};
+use crate::shape::{Shape, CLASSIC, SHAPES};
+use std::sync::OnceLock;
+
+const BOARD_LENGTH: usize = 9;
+pub const ALL_CANDIDATES: u16 = (1 << BOARD_LENGTH) - 1;
+
+// the biggest board we can solve is 16x16, masks have a bit for every digit
+const MAX_LENGTH: usize = 16;
+const MAX_SIZE: usize = MAX_LENGTH * MAX_LENGTH;
+
+/*
+    What a solution has to satisfy on top of every row, column and box holding each digit once
+*/
+#[derive(Clone, Copy)]
+pub struct Rules<'a> {
+    pub shape: Shape,
+    // killer cages, the digits in a cage are all different and add up to its sum
+    pub cages: &'a [Cage],
+    // both main diagonals also hold each digit once
+    pub diagonal: bool,
+}
+
+impl Rules<'static> {
+    /*
+        Plain sudoku on a board of the given shape
+    */
+    pub fn plain(shape: Shape) -> Rules<'static> {
+        Rules {
+            shape,
+            cages: &[],
+            diagonal: false,
+        }
+    }
+}
+
+impl Rules<'_> {
+    /*
+        Whether a cell is on one of the diagonals that have to hold each digit once
+    */
+    pub fn on_diagonal(&self, index: usize) -> bool {
+        let (down, up) = self.shape.diagonals(index);
+        self.diagonal && (down || up)
+    }
+
+    /*
+        Two different cells that share a row, column, box or ruled diagonal can't hold the same
+        digit. Cages are left to the caller, which can look them up with `cage_index`
+    */
+    pub fn shares_unit(&self, a: usize, b: usize) -> bool {
+        let (down_a, up_a) = self.shape.diagonals(a);
+        let (down_b, up_b) = self.shape.diagonals(b);
+        let same_diagonal = self.diagonal && ((down_a && down_b) || (up_a && up_b));
+        self.shape.sees(a, b) || (a != b && same_diagonal)
+    }
+}
+
+/*
+    Everything about the rules that stays the same while searching, worked out once per solve
+*/
+struct Context<'a> {
+    shape: Shape,
+    all_candidates: u16,
+    cages: &'a [Cage],
+    // the cage each cell belongs to, if any
+    cage_of: Vec<Option<usize>>,
+    // the diagonals that have to hold each digit once, empty unless the rules ask for them
+    diagonals: Vec<Vec<usize>>,
+}
+
+impl<'a> Context<'a> {
+    fn new(rules: Rules<'a>) -> Context<'a> {
+        Context {
+            shape: rules.shape,
+            all_candidates: ((1u32 << rules.shape.size()) - 1) as u16,
+            cages: rules.cages,
+            cage_of: cage_index(rules.shape, rules.cages),
+            diagonals: if rules.diagonal {
+                rules.shape.diagonal_units().to_vec()
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+/*
+    A board that tracks which digits are already used in every row, column, box, cage and diagonal
+
+    Digits are stored as 1-16 with 0 meaning empty, bit (d - 1) of a mask is set when d is used.
+    The arrays are sized for the biggest board so boards stay cheap to copy while searching
+*/
+#[derive(Clone, Copy)]
+struct Board<'a> {
+    context: &'a Context<'a>,
+    cells: [u8; MAX_SIZE],
+    rows: [u16; MAX_LENGTH],
+    cols: [u16; MAX_LENGTH],
+    boxes: [u16; MAX_LENGTH],
+    cages: [u16; MAX_SIZE],
+    diagonals: [u16; 2],
+}
+
+impl<'a> Board<'a> {
+    fn empty(context: &'a Context<'a>) -> Board<'a> {
+        Board {
+            context,
+            cells: [0; MAX_SIZE],
+            rows: [0; MAX_LENGTH],
+            cols: [0; MAX_LENGTH],
+            boxes: [0; MAX_LENGTH],
+            cages: [0; MAX_SIZE],
+            diagonals: [0; 2],
+        }
+    }
+
+    /*
+        Build a board from a grid, returns None if the givens already break the rules
+    */
+    fn from_grid(context: &'a Context<'a>, grid: &[char]) -> Option<Board<'a>> {
+        let mut board = Board::empty(context);
+        for (i, c) in grid.iter().enumerate() {
+            if let Some(digit) = context.shape.digit_of(*c) {
+                if board.candidates(i) & bit(digit as u8) == 0 {
+                    return None;
+                }
+                board.place(i, digit as u8);
+            }
+        }
+        Some(board)
+    }
+
+    fn to_grid(self) -> Vec<char> {
+        let shape = self.context.shape;
+        self.cells[..shape.cell_count()]
+            .iter()
+            .map(|cell| match cell {
+                0 => EMPTY_SPACE,
+                digit => shape.symbol(*digit as usize),
+            })
+            .collect()
+    }
+
+    fn place(&mut self, index: usize, digit: u8) {
+        let (row, col, square) = self.context.shape.unit_indices(index);
+        self.cells[index] = digit;
+        self.rows[row] |= bit(digit);
+        self.cols[col] |= bit(digit);
+        self.boxes[square] |= bit(digit);
+        if let Some(cage) = self.context.cage_of[index] {
+            self.cages[cage] |= bit(digit);
+        }
+        for d in self.diagonals_of(index) {
+            self.diagonals[d] |= bit(digit);
+        }
+    }
+
+    /*
+        The diagonals the cell lies on, if the rules have any
+    */
+    fn diagonals_of(&self, index: usize) -> impl Iterator<Item = usize> {
+        let (down, up) = self.context.shape.diagonals(index);
+        let ruled = !self.context.diagonals.is_empty();
+        [ruled && down, ruled && up]
+            .into_iter()
+            .enumerate()
+            .filter_map(|(d, on)| on.then_some(d))
+    }
+
+    /*
+        The digits that could still legally be placed in the given cell
+    */
+    fn candidates(&self, index: usize) -> u16 {
+        match self.context.cage_of[index] {
+            Some(cage) => self.cage_candidates(cage, index),
+            None => self.unit_candidates(index),
+        }
+    }
+
+    /*
+        The digits not yet used in the cell's row, column, box or diagonals
+    */
+    fn unit_candidates(&self, index: usize) -> u16 {
+        let (row, col, square) = self.context.shape.unit_indices(index);
+        let mut used = self.rows[row] | self.cols[col] | self.boxes[square];
+        for d in self.diagonals_of(index) {
+            used |= self.diagonals[d];
+        }
+        !used & self.context.all_candidates
+    }
+
+    /*
+        The digits that could go in an empty cell of a cage, each one has to leave a sum that
+        the cage's other empty cells can still make with digits of their own
+    */
+    fn cage_candidates(&self, cage: usize, index: usize) -> u16 {
+        let cells = &self.context.cages[cage].cells;
+        let used = self.cages[cage];
+        let placed: u32 = cells.iter().map(|&cell| self.cells[cell] as u32).sum();
+        let remaining = match self.context.cages[cage].sum.checked_sub(placed) {
+            Some(remaining) => remaining,
+            None => return 0,
+        };
+
+        // no digit repeats in a cage, so one bigger than a row can never be filled
+        let mut others = [0; MAX_LENGTH];
+        let mut count = 0;
+        for &cell in cells {
+            if cell != index && self.cells[cell] == 0 {
+                if count == MAX_LENGTH {
+                    return 0;
+                }
+                others[count] = self.unit_candidates(cell) & !used;
+                count += 1;
+            }
+        }
+
+        let mut candidates = 0;
+        let mut digits = self.unit_candidates(index) & !used;
+        while digits != 0 {
+            let digit = digit_of(digits & digits.wrapping_neg());
+            digits &= digits - 1;
+            if digit as u32 > remaining {
+                break;
+            }
+            if can_make_sum(
+                &others[..count],
+                remaining - digit as u32,
+                used | bit(digit),
+            ) {
+                candidates |= bit(digit);
+            }
+        }
+        candidates
+    }
+
+    /*
+        Repeatedly fill in naked and hidden singles until nothing changes
+
+        Returns false if the board reached a contradiction
+    */
+    fn propagate(&mut self) -> bool {
+        let context = self.context;
+        let units = units_of(context.shape).iter().chain(&context.diagonals);
+        loop {
+            let mut progress = false;
+
+            // naked singles, a cell with only one possible digit
+            for i in 0..self.context.shape.cell_count() {
+                if self.cells[i] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(i);
+                match candidates.count_ones() {
+                    0 => return false,
+                    1 => {
+                        self.place(i, digit_of(candidates));
+                        progress = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            // hidden singles, a digit with only one possible cell in a unit
+            for unit in units.clone() {
+                let mut seen_once = 0u16;
+                let mut seen_twice = 0u16;
+                let mut placed = 0u16;
+                for &i in unit {
+                    if self.cells[i] != 0 {
+                        placed |= bit(self.cells[i]);
+                        continue;
+                    }
+                    let candidates = self.candidates(i);
+                    seen_twice |= seen_once & candidates;
+                    seen_once |= candidates;
+                }
+
+                if (seen_once | placed) != self.context.all_candidates {
+                    // some digit has nowhere left to go in this unit
+                    return false;
+                }
+
+                let hidden = seen_once & !seen_twice & !placed;
+                if hidden == 0 {
+                    continue;
+                }
+                for &i in unit {
+                    if self.cells[i] != 0 {
+                        continue;
+                    }
+                    let single = self.candidates(i) & hidden;
+                    if single != 0 {
+                        if single.count_ones() > 1 {
+                            return false;
+                        }
+                        self.place(i, digit_of(single));
+                        progress = true;
+                    }
+                }
+            }
+
+            if !progress {
+                return true;
+            }
+        }
+    }
+
+    /*
+        Find the empty cell with the fewest candidates, None if the board is full
+    */
+    fn most_constrained_cell(&self) -> Option<usize> {
+        let mut best: Option<(usize, u32)> = None;
+        for i in 0..self.context.shape.cell_count() {
+            if self.cells[i] != 0 {
+                continue;
+            }
+            let count = self.candidates(i).count_ones();
+            if best.is_none_or(|(_, best_count)| count < best_count) {
+                best = Some((i, count));
+                if count <= 2 {
+                    break;
+                }
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+}
+
+/*
+    Solve a grid under the given rules, returns the first solution found or None if it has no solution
+*/
+pub fn solve(rules: Rules, grid: &[char]) -> Option<Vec<char>> {
+    grid_solutions(rules, grid, 1).pop()
+}
+
+/*
+    Find up to `limit` distinct solutions to the puzzle
+*/
+pub fn solutions(puzzle: &SudokuPuzzle, limit: usize) -> Vec<SudokuPuzzle> {
+    grid_solutions(Rules::plain(CLASSIC), puzzle, limit)
+        .into_iter()
+        .map(|solution| solution.try_into().unwrap())
+        .collect()
+}
+
+/*
+    Find up to `limit` distinct solutions to a grid of any shape
+*/
+pub fn grid_solutions(rules: Rules, grid: &[char], limit: usize) -> Vec<Vec<char>> {
+    let mut found = vec![];
+    if limit == 0 || grid.len() != rules.shape.cell_count() {
+        return found;
+    }
+    let context = Context::new(rules);
+    if let Some(board) = Board::from_grid(&context, grid) {
+        search(board, limit, &mut found);
+    }
+    found
+}
+
+/*
+    Count the solutions to the grid, stopping once `limit` have been found
+
+    A well formed puzzle has exactly one solution so a limit of 2 is enough to check uniqueness
+*/
+pub fn count_solutions(rules: Rules, grid: &[char], limit: usize) -> usize {
+    grid_solutions(rules, grid, limit).len()
+}
+
+/*
+    The digits each empty cell could still hold given the digits already placed, 0 for filled cells
+
+    Unlike solving, this doesn't care if the placed digits break the rules
+*/
+pub fn candidates(rules: Rules, grid: &[char]) -> Vec<u16> {
+    let context = Context::new(rules);
+    let mut board = Board::empty(&context);
+    for (i, c) in grid.iter().enumerate() {
+        if let Some(digit) = rules.shape.digit_of(*c) {
+            board.place(i, digit as u8);
+        }
+    }
+
+    grid.iter()
+        .enumerate()
+        .map(|(i, c)| {
+            if *c == EMPTY_SPACE {
+                board.candidates(i)
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+/*
+    Depth first search, propagating constraints at every node and branching on the most constrained cell
+*/
+fn search(mut board: Board, limit: usize, found: &mut Vec<Vec<char>>) {
+    if !board.propagate() {
+        return;
+    }
+
+    let index = match board.most_constrained_cell() {
+        Some(index) => index,
+        None => {
+            found.push(board.to_grid());
+            return;
+        }
+    };
+
+    let mut candidates = board.candidates(index);
+    while candidates != 0 && found.len() < limit {
+        let digit = digit_of(candidates & candidates.wrapping_neg());
+        candidates &= candidates - 1;
+
+        let mut next = board;
+        next.place(index, digit);
+        search(next, limit, found);
+    }
+}
+
+pub fn bit(digit: u8) -> u16 {
+    1 << (digit - 1)
+}
+
+/*
+    Whether each cell can be given a different digit from its mask, none of them already
+    `used`, so that they add up to `sum`
+*/
+fn can_make_sum(cells: &[u16], sum: u32, used: u16) -> bool {
+    let (first, rest) = match cells.split_first() {
+        Some(split) => split,
+        None => return sum == 0,
+    };
+    let mut digits = first & !used;
+    while digits != 0 {
+        let digit = digit_of(digits & digits.wrapping_neg());
+        digits &= digits - 1;
+        // digits come out smallest first, so the rest are all too big
+        if digit as u32 > sum {
+            break;
+        }
+        if can_make_sum(rest, sum - digit as u32, used | bit(digit)) {
+            return true;
+        }
+    }
+    false
+}
+
+/*
+    Convert a mask with a single bit set back into its digit
+*/
+pub fn digit_of(mask: u16) -> u8 {
+    mask.trailing_zeros() as u8 + 1
+}
+
+/*
+    Returns the (row, column, box) that a board index belongs to
+*/
+pub fn unit_indices(index: usize) -> (usize, usize, usize) {
+    CLASSIC.unit_indices(index)
+}
+
+/*
+    Two different cells see each other if they share a row, column or box
+*/
+pub fn sees(a: usize, b: usize) -> bool {
+    CLASSIC.sees(a, b)
+}
+
+/*
+    Every row, column and box as lists of board indices, built once and shared
+*/
+pub fn all_units() -> &'static [[usize; BOARD_LENGTH]] {
+    static UNITS: OnceLock<Vec<[usize; BOARD_LENGTH]>> = OnceLock::new();
+    UNITS.get_or_init(|| {
+        units_of(CLASSIC)
+            .iter()
+            .map(|unit| unit.as_slice().try_into().unwrap())
+            .collect()
+    })
+}
+
+/*
+    The units of a board of any shape, built once per shape
+*/
+fn units_of(shape: Shape) -> &'static [Vec<usize>] {
+    static UNITS: OnceLock<Vec<Vec<Vec<usize>>>> = OnceLock::new();
+    let all = UNITS.get_or_init(|| {
+        SHAPES
+            .iter()
+            .map(|shape| (0..shape.unit_count()).map(|u| shape.unit(u)).collect())
+            .collect()
+    });
+    let index = SHAPES.iter().position(|s| *s == shape).unwrap();
+    &all[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(grid: &str) -> SudokuPuzzle {
+        let mut puzzle = [EMPTY_SPACE; 81];
+        for (i, c) in grid.chars().enumerate() {
+            if c != '.' {
+                puzzle[i] = c;
+            }
+        }
+        puzzle
+    }
+
+    fn is_valid_solution(solution: &SudokuPuzzle) -> bool {
+        all_units().iter().all(|unit| {
+            let mut seen = 0u16;
+            for &i in unit {
+                match solution[i].to_digit(10) {
+                    Some(d) if d > 0 => seen |= bit(d as u8),
+                    _ => return false,
+                }
+            }
+            seen == ALL_CANDIDATES
+        })
+    }
+
+    #[test]
+    fn solves_hard_puzzle() {
+        let puzzle = parse(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        );
+        let solution: SudokuPuzzle = solve(Rules::plain(CLASSIC), &puzzle)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(is_valid_solution(&solution));
+        for (a, b) in puzzle.iter().zip(solution.iter()) {
+            if *a != EMPTY_SPACE {
+                assert_eq!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_board_has_many_solutions() {
+        let puzzle = [EMPTY_SPACE; 81];
+        let found = solutions(&puzzle, 5);
+        assert_eq!(found.len(), 5);
+        assert!(found.iter().all(is_valid_solution));
+    }
+
+    #[test]
+    fn count_stops_at_limit() {
+        let puzzle = [EMPTY_SPACE; 81];
+        assert_eq!(count_solutions(Rules::plain(CLASSIC), &puzzle, 0), 0);
+        assert_eq!(count_solutions(Rules::plain(CLASSIC), &puzzle, 2), 2);
+    }
+
+    #[test]
+    fn candidates_follow_placed_digits() {
+        let mut puzzle = [EMPTY_SPACE; 81];
+        puzzle[0] = '1';
+        puzzle[80] = '9';
+        let candidates = candidates(Rules::plain(CLASSIC), &puzzle);
+        assert_eq!(candidates[0], 0);
+        assert_eq!(candidates[1], ALL_CANDIDATES & !bit(1));
+        assert_eq!(candidates[72], ALL_CANDIDATES & !bit(1) & !bit(9));
+        assert_eq!(candidates[40], ALL_CANDIDATES);
+    }
+
+    #[test]
+    fn diagonals_limit_candidates() {
+        let rules = Rules {
+            diagonal: true,
+            ..Rules::plain(CLASSIC)
+        };
+        let mut puzzle = [EMPTY_SPACE; 81];
+        puzzle[0] = '1';
+        puzzle[8] = '9';
+        let candidates = candidates(rules, &puzzle);
+        assert_eq!(candidates[40], ALL_CANDIDATES & !bit(1) & !bit(9));
+        assert_eq!(candidates[80], ALL_CANDIDATES & !bit(1) & !bit(9));
+        assert_eq!(candidates[41], ALL_CANDIDATES);
+
+        puzzle[80] = '1';
+        assert_eq!(solve(rules, &puzzle), None);
+        assert!(rules.shares_unit(0, 80) && !rules.shares_unit(0, 79));
+    }
+
+    #[test]
+    fn conflicting_givens_have_no_solution() {
+        let mut puzzle = [EMPTY_SPACE; 81];
+        puzzle[0] = '5';
+        puzzle[8] = '5';
+        assert_eq!(solve(Rules::plain(CLASSIC), &puzzle), None);
+    }
+
+    #[test]
+    fn cages_limit_candidates() {
+        let cages = [
+            Cage {
+                cells: vec![0, 1, 2],
+                sum: 24,
+            },
+            Cage {
+                cells: vec![9, 10],
+                sum: 10,
+            },
+        ];
+        let rules = Rules {
+            shape: CLASSIC,
+            cages: &cages,
+            diagonal: false,
+        };
+        let mut puzzle = [EMPTY_SPACE; 81];
+        puzzle[9] = '1';
+        let candidates = candidates(rules, &puzzle);
+        assert_eq!(candidates[0], bit(7) | bit(8) | bit(9));
+        assert_eq!(candidates[10], bit(9));
+        assert_eq!(candidates[11], ALL_CANDIDATES & !bit(1));
+
+        puzzle[10] = '8';
+        assert_eq!(solve(rules, &puzzle), None);
+    }
+}
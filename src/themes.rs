@@ -5,7 +5,10 @@ pub struct Theme {
     pub dark_square_color: Color,
     pub light_square_color: Color,
     pub highlighted_color: Color,
+    pub hint_color: Color,
+    pub diagonal_color: Color,
     pub error_color: Color,
+    #[allow(dead_code)]
     pub text_color: Color,
     pub light_number_color: Color,
     pub dark_number_color: Color,
@@ -17,6 +20,8 @@ pub const TRANQUIL: Theme = Theme {
     dark_square_color: Color::Gray,
     light_square_color: Color::White,
     highlighted_color: Color::Rgb(184, 255, 184), // Mint
+    hint_color: Color::Rgb(174, 198, 235),        // Pastel Blue
+    diagonal_color: Color::Rgb(253, 253, 186),    // Pastel Yellow
     error_color: Color::Rgb(255, 165, 161),       // Pastel Red
     text_color: Color::White,
     light_number_color: Color::Black,
@@ -30,6 +35,8 @@ pub const DRACULA: Theme = Theme {
     dark_square_color: Color::Rgb(68, 71, 90),
     light_square_color: Color::Rgb(40, 42, 54),
     highlighted_color: Color::Rgb(189, 147, 249),
+    hint_color: Color::Rgb(98, 114, 164),
+    diagonal_color: Color::Rgb(86, 72, 102),
     error_color: Color::Rgb(255, 85, 85),
     text_color: Color::Rgb(248, 248, 242),
     light_number_color: Color::Rgb(248, 248, 242),
@@ -1,7 +1,17 @@
 use crate::events::{Event, Events, TICK_RATE_MS};
-use crate::puzzle::{Difficulty, Puzzle, SudokuPuzzle, EMPTY_SPACE};
+use crate::history::{CellState, History, Move};
+use crate::logic::{self, Hint};
+use crate::solver::candidates;
+use crate::puzzle::{Difficulty, Puzzle, PuzzleId, EMPTY_SPACE};
+use crate::shape::Shape;
+use crate::killer::{cage_index, Cage};
+use crate::daily::Date;
+use crate::formats;
+use crate::pack::{PackProgress, PuzzlePack};
+use crate::save::{self, SavedGame};
 use crate::themes::{Theme, TRANQUIL, DRACULA};
 
+use std::env;
 use std::io::{self, Stdout};
 use termion::{
     event::Key,
@@ -18,12 +28,14 @@ use tui::{
     Frame, Terminal,
 };
 
-const BOARD_LENGTH: usize = 9;
-const PUZZLE_WIDTH: u16 = 54;
-const PUZZLE_HEIGHT: u16 = 27;
+// every cell is drawn 6 wide and 3 tall, so a 9x9 board is 54 by 27
+const CELL_WIDTH: u16 = 6;
+const CELL_HEIGHT: u16 = 3;
+// the info and controls windows never get narrower than a 9x9 board
+const WINDOW_WIDTH: u16 = 54;
 
 const CONTROLS: &str =
-    "Select cell: hjkl | ← ↓ ↑ →\nErase cell: space\nStart new puzzle(Beginner, Easy, Hard): z, x, c\nGive up: g\nQuit: q | Ctrl-C";
+    "Select cell: hjkl | ← ↓ ↑ → | Digits: 1-9, A-G\nErase: space | Notes mode: p | Fill notes: a\nNew puzzle(Beginner to Diabolical): z, x, c, v, b, n\nUndo: u | Redo: Ctrl-r | Hint: ? | Give up: g\nExport: e | Next/prev puzzle: ] [ | Quit: q | Ctrl-C";
 
 #[derive(PartialEq)]
 pub struct Point {
@@ -38,8 +50,8 @@ impl Point {
         }
     }
 
-    pub fn right(&mut self) {
-        if self.x != BOARD_LENGTH - 1 {
+    pub fn right(&mut self, length: usize) {
+        if self.x != length - 1 {
             self.x += 1;
         }
     }
@@ -50,14 +62,14 @@ impl Point {
         }
     }
 
-    pub fn down(&mut self) {
-        if self.y != BOARD_LENGTH - 1 {
+    pub fn down(&mut self, length: usize) {
+        if self.y != length - 1 {
             self.y += 1;
         }
     }
 
-    pub fn as_board_cords(&self) -> usize {
-        self.x + BOARD_LENGTH * self.y
+    pub fn as_board_cords(&self, length: usize) -> usize {
+        self.x + length * self.y
     }
 }
 
@@ -67,32 +79,126 @@ type SudokuFrame<'a> =
 pub struct UI {
     puzzle: Puzzle,
     theme: Theme,
-    displayed_puzzle: SudokuPuzzle,
+    displayed_puzzle: Vec<char>,
+    // pencil marks for every cell, bit (d - 1) is set when d is noted
+    notes: Vec<u16>,
+    notes_mode: bool,
+    history: History,
     highlighted_cell: Point,
-    cell_counts: [u8; BOARD_LENGTH],
+    cell_counts: Vec<u8>,
     time_in_ms: u64,
     gave_up: bool,
     has_won: bool,
+    hint: Option<Hint>,
+    // a one off message for the player, cleared on the next key press
+    message: Option<String>,
+    // the collection being played through, if the puzzle came from one
+    pack: Option<PuzzlePack>,
+    // where the player was in a pack that couldn't be loaded, kept so it is saved again
+    unloaded_pack: Option<PackProgress>,
+    // the cage each cell belongs to, worked out once per puzzle instead of on every frame
+    cage_of: Vec<Option<usize>>,
+    // the day this puzzle is the daily puzzle for
+    daily: Option<Date>,
 }
 
 impl UI {
-    pub fn new() -> UI {
-        let new_puzzle = Puzzle::new_puzzle(Difficulty::Beginner);
-        let displayed_puzzle = new_puzzle.puzzle;
+    pub fn new(difficulty: Difficulty) -> UI {
+        UI::with_puzzle(Puzzle::new_puzzle(difficulty))
+    }
+
+    pub fn with_puzzle(new_puzzle: Puzzle) -> UI {
+        let displayed_puzzle = new_puzzle.puzzle.clone();
+        let notes = vec![0; new_puzzle.shape.cell_count()];
+        let mut ui = UI::from_save(SavedGame {
+            puzzle: new_puzzle,
+            displayed_puzzle,
+            notes,
+            time_in_ms: 0,
+            gave_up: false,
+            has_won: false,
+            pack: None,
+            daily: None,
+        });
+        ui.check_played();
+        ui
+    }
+
+    /*
+        Play the puzzle of the day, the same date always gives the same puzzle
+    */
+    pub fn daily(date: Date, difficulty: Difficulty) -> UI {
+        let mut ui = UI::with_puzzle(Puzzle::from_id(date.puzzle_id(difficulty)));
+        ui.daily = Some(date);
+        ui
+    }
+
+    /*
+        Play through a collection of puzzles, starting with the first one
+    */
+    pub fn with_pack(pack: PuzzlePack) -> UI {
+        let mut ui = UI::with_puzzle(pack.current().clone());
+        ui.pack = Some(pack);
+        ui
+    }
+
+    /*
+        Resume a game exactly as it was saved
+    */
+    pub fn from_save(game: SavedGame) -> UI {
         let theme : Theme = match dark_light::detect() {
             dark_light::Mode::Dark => DRACULA,
             dark_light::Mode::Light => TRANQUIL,
             dark_light::Mode::Default => TRANQUIL,
         };
+        let cell_counts = vec![0; game.puzzle.shape.size()];
+
+        // a pack that can't be read anymore leaves us with the saved puzzle, but the player
+        // should know why ] and [ stopped working
+        let (pack, unloaded_pack, message) = match game.pack {
+            Some(progress) => match PuzzlePack::resume(&progress) {
+                Ok(pack) => (Some(pack), None, None),
+                Err(err) => {
+                    let message = format!("Could not load the pack {}: {}", progress.path, err);
+                    (None, Some(progress), Some(message))
+                }
+            },
+            None => (None, None, None),
+        };
         UI {
-            puzzle: new_puzzle,
+            cage_of: cage_index(game.puzzle.shape, &game.puzzle.cages),
+            puzzle: game.puzzle,
             theme,
-            displayed_puzzle,
+            displayed_puzzle: game.displayed_puzzle,
+            notes: game.notes,
+            notes_mode: false,
+            history: History::new(),
             highlighted_cell: Point { x: 0, y: 0 },
-            cell_counts: [0; BOARD_LENGTH],
-            time_in_ms: 0,
-            gave_up: false,
-            has_won: false,
+            cell_counts,
+            time_in_ms: game.time_in_ms,
+            gave_up: game.gave_up,
+            has_won: game.has_won,
+            hint: None,
+            message,
+            pack,
+            unloaded_pack,
+            daily: game.daily,
+        }
+    }
+
+    pub fn to_save(&self) -> SavedGame {
+        SavedGame {
+            puzzle: self.puzzle.clone(),
+            displayed_puzzle: self.displayed_puzzle.clone(),
+            notes: self.notes.clone(),
+            time_in_ms: self.time_in_ms,
+            gave_up: self.gave_up,
+            has_won: self.has_won,
+            pack: match &self.pack {
+                Some(pack) => pack.progress(),
+                None => self.unloaded_pack.clone(),
+            },
+            daily: self.daily,
         }
     }
 
@@ -117,39 +223,70 @@ impl UI {
                 })
                 .unwrap();
 
+            self.record_win();
+
             let event = events.next().unwrap();
+            if let Event::Input(_) = event {
+                self.message = None;
+            }
+
             if self.gave_up || self.has_won {
                 match event {
                     Event::Input(Key::Char('z')) => self.new_game(Difficulty::Beginner),
                     Event::Input(Key::Char('x')) => self.new_game(Difficulty::Easy),
-                    Event::Input(Key::Char('c')) => self.new_game(Difficulty::Hard),
+                    Event::Input(Key::Char('c')) => self.new_game(Difficulty::Medium),
+                    Event::Input(Key::Char('v')) => self.new_game(Difficulty::Hard),
+                    Event::Input(Key::Char('b')) => self.new_game(Difficulty::Expert),
+                    Event::Input(Key::Char('n')) => self.new_game(Difficulty::Diabolical),
+                    Event::Input(Key::Char('e')) => {
+                        self.export();
+                        continue;
+                    }
+                    Event::Input(Key::Char(']')) => {
+                        self.next_in_pack();
+                        continue;
+                    }
+                    Event::Input(Key::Char('[')) => {
+                        self.previous_in_pack();
+                        continue;
+                    }
                     Event::Input(Key::Char('q')) | Event::Input(Key::Ctrl('c')) => break,
                     _ => continue,
                 }
             }
 
+            let length = self.puzzle.shape.size();
             match event {
                 Event::Input(key) => {
                     match key {
                         // movement using arrow keys or vim movement keys
                         Key::Up | Key::Char('k') => self.highlighted_cell.up(),
-                        Key::Down | Key::Char('j') => self.highlighted_cell.down(),
+                        Key::Down | Key::Char('j') => self.highlighted_cell.down(length),
                         Key::Left | Key::Char('h') => self.highlighted_cell.left(),
-                        Key::Right | Key::Char('l') => self.highlighted_cell.right(),
-                        Key::Char('1') => self.update_displayed_board('1'),
-                        Key::Char('2') => self.update_displayed_board('2'),
-                        Key::Char('3') => self.update_displayed_board('3'),
-                        Key::Char('4') => self.update_displayed_board('4'),
-                        Key::Char('5') => self.update_displayed_board('5'),
-                        Key::Char('6') => self.update_displayed_board('6'),
-                        Key::Char('7') => self.update_displayed_board('7'),
-                        Key::Char('8') => self.update_displayed_board('8'),
-                        Key::Char('9') => self.update_displayed_board('9'),
-                        Key::Char(' ') => self.update_displayed_board(EMPTY_SPACE),
+                        Key::Right | Key::Char('l') => self.highlighted_cell.right(length),
+                        Key::Char(digit) if self.notes_mode && self.is_digit(digit) => {
+                            self.edit(|ui| ui.toggle_note(digit))
+                        }
+                        Key::Char(digit) if self.is_digit(digit) => {
+                            self.edit(|ui| ui.update_displayed_board(digit))
+                        }
+                        Key::Char(' ') if self.notes_mode => self.edit(UI::clear_notes),
+                        Key::Char(' ') => self.edit(|ui| ui.update_displayed_board(EMPTY_SPACE)),
+                        Key::Char('p') => self.notes_mode = !self.notes_mode,
+                        Key::Char('a') => self.edit(UI::fill_notes),
+                        Key::Char('u') => self.undo(),
+                        Key::Ctrl('r') => self.redo(),
                         Key::Char('z') => self.new_game(Difficulty::Beginner),
                         Key::Char('x') => self.new_game(Difficulty::Easy),
-                        Key::Char('c') => self.new_game(Difficulty::Hard),
+                        Key::Char('c') => self.new_game(Difficulty::Medium),
+                        Key::Char('v') => self.new_game(Difficulty::Hard),
+                        Key::Char('b') => self.new_game(Difficulty::Expert),
+                        Key::Char('n') => self.new_game(Difficulty::Diabolical),
+                        Key::Char('?') => self.show_hint(),
                         Key::Char('g') => self.give_up(),
+                        Key::Char('e') => self.export(),
+                        Key::Char(']') => self.next_in_pack(),
+                        Key::Char('[') => self.previous_in_pack(),
                         Key::Char('q') | Key::Ctrl('c') => break,
                         _ => {}
                     }
@@ -160,24 +297,248 @@ impl UI {
     }
 
     fn new_game(&mut self, difficulty: Difficulty) {
+        // a random puzzle takes us out of the pack, but keeps the board size and variant
+        self.pack = None;
+        self.unloaded_pack = None;
+        self.start_game(Puzzle::from_id(PuzzleId::random(
+            self.puzzle.shape,
+            self.puzzle.variant,
+            difficulty,
+        )));
+    }
+
+    /*
+        Boards bigger than 9x9 type 10 and up as capital letters, so they never clash with
+        the controls
+    */
+    fn is_digit(&self, key: char) -> bool {
+        !key.is_ascii_lowercase() && self.puzzle.shape.digit_of(key).is_some()
+    }
+
+    /*
+        The cage a cell belongs to, if any
+    */
+    fn cage(&self, index: usize) -> Option<&Cage> {
+        self.cage_of[index].map(|c| &self.puzzle.cages[c])
+    }
+
+    /*
+        Two different cells see each other if they can't hold the same digit, because they
+        share a row, column, box, cage or diagonal
+    */
+    fn sees(&self, a: usize, b: usize) -> bool {
+        let same_cage = self.cage_of[a].is_some() && self.cage_of[a] == self.cage_of[b];
+        self.puzzle.rules().shares_unit(a, b) || (a != b && same_cage)
+    }
+
+    fn next_in_pack(&mut self) {
+        if let Some(pack) = &mut self.pack {
+            if pack.next() {
+                let puzzle = pack.current().clone();
+                self.start_game(puzzle);
+            } else {
+                self.message = Some("This is the last puzzle in the pack".to_string());
+            }
+        }
+    }
+
+    fn previous_in_pack(&mut self) {
+        if let Some(pack) = &mut self.pack {
+            if pack.previous() {
+                let puzzle = pack.current().clone();
+                self.start_game(puzzle);
+            } else {
+                self.message = Some("This is the first puzzle in the pack".to_string());
+            }
+        }
+    }
+
+    fn start_game(&mut self, puzzle: Puzzle) {
+        self.daily = None;
         self.time_in_ms = 0;
-        self.puzzle = Puzzle::new_puzzle(difficulty);
-        self.displayed_puzzle = self.puzzle.puzzle;
+        self.cage_of = cage_index(puzzle.shape, &puzzle.cages);
+        self.puzzle = puzzle;
+        self.displayed_puzzle = self.puzzle.puzzle.clone();
+        self.notes = vec![0; self.puzzle.shape.cell_count()];
+        self.cell_counts = vec![0; self.puzzle.shape.size()];
+        self.highlighted_cell = Point { x: 0, y: 0 };
+        self.history.clear();
         self.gave_up = false;
         self.has_won = false;
+        self.hint = None;
+        self.check_played();
 
         // cell counts will be updated automatically on the next frame render
     }
 
+    /*
+        Let the player know if they're starting a puzzle they've already played, even in disguise
+    */
+    fn check_played(&mut self) {
+        // only 9x9 puzzles have a canonical form to remember them by
+        let played = self.puzzle.classic_grid().map(|grid| save::record_played(&grid));
+        if let Some(Ok(true)) = played {
+            self.message = Some("You've played this puzzle before".to_string());
+        }
+    }
+
+    /*
+        Run an edit on the board and record every cell it changed so it can be undone
+    */
+    fn edit(&mut self, change: impl FnOnce(&mut UI)) {
+        let before = self.cell_states();
+        change(self);
+        let after = self.cell_states();
+
+        let changes = (0..self.puzzle.shape.cell_count())
+            .filter(|&i| before[i] != after[i])
+            .map(|i| (i, before[i], after[i]))
+            .collect();
+        self.history.record(Move { changes });
+    }
+
+    fn cell_states(&self) -> Vec<CellState> {
+        self.displayed_puzzle
+            .iter()
+            .zip(self.notes.iter())
+            .map(|(value, notes)| CellState {
+                value: *value,
+                notes: *notes,
+            })
+            .collect()
+    }
+
+    fn undo(&mut self) {
+        if let Some(change) = self.history.undo() {
+            for &(i, before, _) in &change.changes {
+                self.displayed_puzzle[i] = before.value;
+                self.notes[i] = before.notes;
+            }
+            self.hint = None;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(change) = self.history.redo() {
+            for &(i, _, after) in &change.changes {
+                self.displayed_puzzle[i] = after.value;
+                self.notes[i] = after.notes;
+            }
+            self.hint = None;
+        }
+    }
+
     fn update_displayed_board(&mut self, val: char) {
-        if self.puzzle.puzzle[self.highlighted_cell.as_board_cords()] == EMPTY_SPACE {
-            self.displayed_puzzle[self.highlighted_cell.as_board_cords()] = val;
+        let shape = self.puzzle.shape;
+        let index = self.highlighted_cell.as_board_cords(shape.size());
+        if self.puzzle.puzzle[index] == EMPTY_SPACE {
+            self.displayed_puzzle[index] = val;
+            self.hint = None;
+
+            // the digit can no longer be a candidate anywhere it can be seen from
+            if let Some(digit) = shape.digit_of(val) {
+                for other in 0..shape.cell_count() {
+                    if self.sees(index, other) {
+                        self.notes[other] &= !(1 << (digit - 1));
+                    }
+                }
+            }
+        }
+    }
+
+    /*
+        Add or remove a pencil mark in the highlighted cell, only empty cells can have notes
+    */
+    fn toggle_note(&mut self, val: char) {
+        let shape = self.puzzle.shape;
+        let index = self.highlighted_cell.as_board_cords(shape.size());
+        if self.displayed_puzzle[index] != EMPTY_SPACE {
+            return;
+        }
+        if let Some(digit) = shape.digit_of(val) {
+            self.notes[index] ^= 1 << (digit - 1);
+        }
+    }
+
+    fn clear_notes(&mut self) {
+        let index = self.highlighted_cell.as_board_cords(self.puzzle.shape.size());
+        self.notes[index] = 0;
+    }
+
+    /*
+        Replace the notes in every empty cell with all the digits that could legally go there
+    */
+    fn fill_notes(&mut self) {
+        self.notes = candidates(self.puzzle.rules(), &self.displayed_puzzle);
+    }
+
+    /*
+        Point out any mistakes first, otherwise explain the next logical step
+    */
+    fn show_hint(&mut self) {
+        let mistakes: Vec<usize> = (0..self.puzzle.shape.cell_count())
+            .filter(|&i| self.displayed_puzzle[i] != EMPTY_SPACE)
+            .filter(|&i| self.displayed_puzzle[i] != self.puzzle.solution[i])
+            .collect();
+
+        self.hint = if !mistakes.is_empty() {
+            Some(Hint {
+                cells: mistakes,
+                description: "These cells don't match the solution".to_string(),
+            })
+        } else {
+            let hint = match self.puzzle.classic_grid() {
+                Some(_) => logic::hint(&self.displayed_puzzle.as_slice().try_into().unwrap()),
+                None => logic::single_hint(self.puzzle.rules(), &self.displayed_puzzle),
+            };
+            Some(hint.unwrap_or(Hint {
+                cells: vec![],
+                description: "No logical step found, time to guess".to_string(),
+            }))
+        };
+    }
+
+    /*
+        Write the board out in every text format we know, so it can be shared
+    */
+    fn export(&mut self) {
+        let result = match save::data_dir() {
+            Some(dir) => formats::export(&dir, &self.puzzle, &self.displayed_puzzle).map(|_| dir),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no data directory",
+            )),
+        };
+
+        self.message = Some(match result {
+            Ok(dir) => {
+                let dir = dir.display().to_string();
+                let home = env::var("HOME").unwrap_or_default();
+                match dir.strip_prefix(&home) {
+                    Some(rest) if !home.is_empty() => format!("Exported to ~{}", rest),
+                    _ => format!("Exported to {}", dir),
+                }
+            }
+            Err(err) => format!("Export failed: {}", err),
+        });
+    }
+
+    /*
+        Count a won puzzle as solved in its pack. Giving up fills the board with the solution,
+        which looks like a win on the next frame, but that puzzle wasn't solved
+    */
+    fn record_win(&mut self) {
+        if self.has_won && !self.gave_up {
+            if let Some(pack) = &mut self.pack {
+                pack.mark_solved();
+            }
         }
     }
 
     fn give_up(&mut self) {
         self.gave_up = true;
-        self.displayed_puzzle = self.puzzle.solution;
+        self.hint = None;
+        self.displayed_puzzle = self.puzzle.solution.clone();
     }
 }
 
@@ -186,7 +547,9 @@ impl UI {
 */
 fn draw_puzzle_window(frame: &mut SudokuFrame, ui: &mut UI) -> bool {
     let terminal_rect = frame.size();
-    ui.cell_counts = [0; BOARD_LENGTH];
+    let shape = ui.puzzle.shape;
+    let (width, height) = board_size(shape);
+    ui.cell_counts = vec![0; shape.size()];
 
     let outer_block = Block::default()
         .borders(Borders::ALL)
@@ -205,7 +568,7 @@ fn draw_puzzle_window(frame: &mut SudokuFrame, ui: &mut UI) -> bool {
     }
 
     // if the window is too small, show warning
-    if terminal_rect.height < PUZZLE_HEIGHT + 2 || terminal_rect.width < PUZZLE_WIDTH + 2 {
+    if terminal_rect.height < height + 2 || terminal_rect.width < width.max(WINDOW_WIDTH) + 2 {
         let text = Paragraph::new("Window is too small\nPlease expand window")
             .alignment(Alignment::Center);
         frame.render_widget(
@@ -222,26 +585,30 @@ fn draw_puzzle_window(frame: &mut SudokuFrame, ui: &mut UI) -> bool {
 
     // draw the sudoku table
     let rect = Rect {
-        x: (frame.size().width - PUZZLE_WIDTH) / 2,
+        x: (frame.size().width - width) / 2,
         y: frame.size().y + 2,
-        width: PUZZLE_WIDTH,
-        height: PUZZLE_HEIGHT,
+        width,
+        height,
     };
 
     let mut found_error = false;
     let mut no_empty_cells = true;
-    let large_table_cells = split_rect_into_three_by_three_square(rect);
+    // the boxes are `box_cols` down and `box_rows` across
+    let large_table_cells = split_rect_into_grid(rect, shape.box_cols, shape.box_rows);
     for (current_square, square) in large_table_cells.into_iter().enumerate() {
-        let cells = split_rect_into_three_by_three_square(square);
+        let cells = split_rect_into_grid(square, shape.box_rows, shape.box_cols);
         for (square_cell_counter, cell) in cells.into_iter().enumerate() {
-            let point_cords = square_to_point_cords(current_square, square_cell_counter);
+            let point_cords = square_to_point_cords(shape, current_square, square_cell_counter);
+            let index = point_cords.as_board_cords(shape.size());
 
             // update cell counts
-            if let Some(num) = ui.displayed_puzzle[point_cords.as_board_cords()].to_digit(10) {
-                ui.cell_counts[num as usize - 1] += 1
+            if let Some(digit) = shape.digit_of(ui.displayed_puzzle[index]) {
+                ui.cell_counts[digit - 1] += 1
             }
 
-            let (mut bg_color, text_color, locked_square_color) = match current_square % 2 {
+            // checkerboard the boxes, counting along both the bands and the stacks
+            let checker = current_square / shape.box_rows + current_square % shape.box_rows;
+            let (mut bg_color, text_color, locked_square_color) = match checker % 2 {
                 0 => (
                     ui.theme.light_square_color,
                     ui.theme.dark_number_color,
@@ -254,20 +621,29 @@ fn draw_puzzle_window(frame: &mut SudokuFrame, ui: &mut UI) -> bool {
                 ),
             };
 
-            let is_err = cell_error(&point_cords, current_square, ui);
+            let is_err = cell_error(&point_cords, ui);
             found_error |= is_err;
 
+            let is_hint = ui
+                .hint
+                .as_ref()
+                .is_some_and(|hint| hint.cells.contains(&index));
+
             if point_cords == ui.highlighted_cell {
                 bg_color = ui.theme.highlighted_color;
+            } else if is_hint {
+                bg_color = ui.theme.hint_color;
             } else if is_err {
                 bg_color = ui.theme.error_color;
+            } else if ui.puzzle.rules().on_diagonal(index) {
+                bg_color = ui.theme.diagonal_color;
             }
 
-            let char = ui.displayed_puzzle[point_cords.as_board_cords()];
+            let char = ui.displayed_puzzle[index];
 
             no_empty_cells &= char != EMPTY_SPACE;
 
-            let fg_color = if ui.puzzle.puzzle[point_cords.as_board_cords()] != EMPTY_SPACE {
+            let fg_color = if ui.puzzle.puzzle[index] != EMPTY_SPACE {
                 locked_square_color
             } else {
                 bg_color
@@ -296,6 +672,37 @@ fn draw_puzzle_window(frame: &mut SudokuFrame, ui: &mut UI) -> bool {
             };
             frame.render_widget(block, cell);
             frame.render_widget(text, text_rect);
+
+            let notes = match char {
+                EMPTY_SPACE => ui.notes[index],
+                _ => 0,
+            };
+            let notes_layout = notes_layout(shape, notes, cell);
+            if notes != 0 {
+                let lines: Vec<String> = notes_layout.iter().map(|l| l.iter().collect()).collect();
+                let notes_text = Paragraph::new(lines.join("\n"))
+                    .style(Style::default().bg(bg_color).fg(text_color));
+                frame.render_widget(notes_text, cell);
+            }
+
+            // the cage outline goes around the notes, so it is drawn after them
+            let cage = ui.cage(index);
+            let cage_style = Style::default().bg(bg_color).fg(text_color);
+            if let Some(cage) = cage {
+                draw_cage_edges(frame, cell, shape, cage, index, &notes_layout, cage_style);
+            }
+
+            // the sum sits in the top left corner of the cage's first cell, above any notes
+            if let Some(cage) = cage.filter(|cage| cage.cells[0] == index) {
+                let label = cage.sum.to_string();
+                let label_rect = Rect {
+                    x: cell.x,
+                    y: cell.y,
+                    width: label.len() as u16,
+                    height: 1,
+                };
+                frame.render_widget(Paragraph::new(label).style(cage_style), label_rect);
+            }
         }
     }
     ui.has_won = !found_error && no_empty_cells;
@@ -303,27 +710,49 @@ fn draw_puzzle_window(frame: &mut SudokuFrame, ui: &mut UI) -> bool {
 }
 
 fn draw_info_window(frame: &mut SudokuFrame, ui: &UI) {
+    let (width, height) = board_size(ui.puzzle.shape);
+    let width = width.max(WINDOW_WIDTH);
+
     // don't render frame if there isn't enough room
-    if frame.size().height <= PUZZLE_HEIGHT + 6 {
+    if frame.size().height <= height + 6 {
         return;
     }
 
     // draw the score window
     let score_window = Rect {
-        x: (frame.size().width - PUZZLE_WIDTH) / 2,
-        y: PUZZLE_HEIGHT + 2,
-        width: PUZZLE_WIDTH,
+        x: (frame.size().width - width) / 2,
+        y: height + 2,
+        width,
         height: 4,
     };
 
+    let title = match &ui.pack {
+        Some(pack) => format!(
+            "Info - Puzzle {}/{}{}, {} solved",
+            pack.index() + 1,
+            pack.puzzle_count(),
+            if pack.is_current_solved() { " ✓" } else { "" },
+            pack.solved_count()
+        ),
+        None => match (ui.daily, ui.puzzle.id) {
+            (Some(date), _) => format!("Info - Daily puzzle {}", date),
+            (None, Some(id)) => format!("Info - Puzzle #{}", id),
+            (None, None) => "Info".to_string(),
+        },
+    };
     let score_block = Block::default().borders(Borders::ALL).title(Span::styled(
-        "Info",
+        title,
         Style::default()
             .fg(ui.theme.title_color)
             .add_modifier(Modifier::BOLD),
     ));
 
-    let mut info_str = if ui.gave_up {
+    let mut info_str = if let Some(message) = &ui.message {
+        vec![Spans::from(Span::styled(
+            message.clone(),
+            Style::default().fg(ui.theme.title_color),
+        ))]
+    } else if ui.gave_up {
         vec![Spans::from(Span::styled(
             "You gave up :(",
             Style::default().fg(ui.theme.error_color),
@@ -333,17 +762,23 @@ fn draw_info_window(frame: &mut SudokuFrame, ui: &UI) {
             "You won, nice job!",
             Style::default().fg(ui.theme.victory_color),
         ))]
+    } else if let Some(hint) = &ui.hint {
+        vec![Spans::from(Span::styled(
+            hint.description.clone(),
+            Style::default().fg(ui.theme.title_color),
+        ))]
     } else {
         let mut counts = "".to_string();
         for (i, val) in ui.cell_counts.iter().enumerate() {
-            counts = format!("{} {}:{}", counts, i + 1, val).to_string();
+            counts = format!("{} {}:{}", counts, ui.puzzle.shape.symbol(i + 1), val).to_string();
         }
         vec![Spans::from(counts)]
     };
 
     info_str.push(Spans::from(format!(
-        "\nDifficulty: {}               Time: {}s",
+        "\nDifficulty: {}     Mode: {}     Time: {}s",
         ui.puzzle.difficulty,
+        if ui.notes_mode { "Notes" } else { "Digits" },
         ui.time_in_ms / 1000
     )));
 
@@ -360,16 +795,19 @@ fn draw_info_window(frame: &mut SudokuFrame, ui: &UI) {
 }
 
 fn draw_controls_window(frame: &mut SudokuFrame, ui: &UI) {
+    let (width, height) = board_size(ui.puzzle.shape);
+    let width = width.max(WINDOW_WIDTH);
+
     // don't render frame if there isn't enough room
-    if frame.size().height <= PUZZLE_HEIGHT + 12 {
+    if frame.size().height <= height + 12 {
         return;
     }
 
     //draw the controls window
     let controls_rect = Rect {
-        x: (frame.size().width - PUZZLE_WIDTH) / 2,
-        y: PUZZLE_HEIGHT + 5,
-        width: PUZZLE_WIDTH,
+        x: (frame.size().width - width) / 2,
+        y: height + 5,
+        width,
         height: 7,
     };
 
@@ -392,96 +830,219 @@ fn draw_controls_window(frame: &mut SudokuFrame, ui: &UI) {
 }
 
 /*
-    Determine if the given cell should display as an error
+    Determine if the given cell should display as an error, which is when its digit is
+    repeated anywhere in the same row, column, box, cage or ruled diagonal, or when its cage
+    can no longer add up to the cage's sum
 */
-fn cell_error(point_cords: &Point, current_square: usize, ui: &UI) -> bool {
-    if ui.displayed_puzzle[point_cords.as_board_cords()] == EMPTY_SPACE {
+fn cell_error(point_cords: &Point, ui: &UI) -> bool {
+    let shape = ui.puzzle.shape;
+    let index = point_cords.as_board_cords(shape.size());
+    let value = ui.displayed_puzzle[index];
+    if value == EMPTY_SPACE {
         return false;
     }
-    let mut counter = 0;
-    // check for any duplicates in the same square
-    let row = current_square - (current_square % BOARD_LENGTH);
-    for i in row..row + BOARD_LENGTH {
-        let current_cord = square_to_point_cords(current_square, i);
-        if ui.displayed_puzzle[point_cords.as_board_cords()]
-            == ui.displayed_puzzle[current_cord.as_board_cords()]
-        {
-            counter += 1;
-        }
-    }
 
-    let mut duplicate_found = counter > 1;
+    let repeated = (0..shape.cell_count())
+        .any(|other| ui.sees(index, other) && ui.displayed_puzzle[other] == value);
+    repeated || ui.cage(index).is_some_and(|cage| cage_error(cage, ui))
+}
 
-    counter = 0;
-    //check for any duplicates in the same col
-    for i in 0..BOARD_LENGTH {
-        let p = Point {
-            x: i,
-            y: point_cords.y,
-        };
-        if ui.displayed_puzzle[p.as_board_cords()]
-            == ui.displayed_puzzle[point_cords.as_board_cords()]
-        {
-            counter += 1;
+/*
+    A cage is wrong once its digits add up to more than its sum, or to less once it is full
+*/
+fn cage_error(cage: &Cage, ui: &UI) -> bool {
+    let digits: Vec<usize> = cage
+        .cells
+        .iter()
+        .filter_map(|&cell| ui.puzzle.shape.digit_of(ui.displayed_puzzle[cell]))
+        .collect();
+    let total = digits.iter().sum::<usize>() as u32;
+    total > cage.sum || (digits.len() == cage.cells.len() && total != cage.sum)
+}
+
+/*
+    Outline a cage with dashed lines along each side of the cell that doesn't lead further
+    into the same cage, leaving any notes in `notes_layout` showing through the gaps
+*/
+fn draw_cage_edges(
+    frame: &mut SudokuFrame,
+    cell: Rect,
+    shape: Shape,
+    cage: &Cage,
+    index: usize,
+    notes_layout: &[Vec<char>],
+    style: Style,
+) {
+    let size = shape.size();
+    let (row, col) = (index / size, index % size);
+    let above = (row > 0).then(|| index - size);
+    let below = (row < size - 1).then(|| index + size);
+    let before = (col > 0).then(|| index - 1);
+    let after = (col < size - 1).then(|| index + 1);
+
+    let top = Rect { height: 1, ..cell };
+    let bottom = Rect {
+        y: cell.y + cell.height - 1,
+        ..top
+    };
+    let left = Rect {
+        y: cell.y + 1,
+        width: 1,
+        height: cell.height - 2,
+        ..cell
+    };
+    let right = Rect {
+        x: cell.x + cell.width - 1,
+        ..left
+    };
+
+    let dash = |note: char, line: char| if note == ' ' { line } else { note };
+    let across = |row: &[char]| row.iter().map(|note| dash(*note, '╌')).collect::<String>();
+    let down = |col: usize| {
+        let rows = &notes_layout[1..notes_layout.len() - 1];
+        let cells: Vec<String> = rows
+            .iter()
+            .map(|row| dash(row[col], '┆').to_string())
+            .collect();
+        cells.join("\n")
+    };
+    let edges = [
+        (above, top, across(&notes_layout[0])),
+        (below, bottom, across(&notes_layout[notes_layout.len() - 1])),
+        (before, left, down(0)),
+        (after, right, down(cell.width as usize - 1)),
+    ];
+    for (neighbour, rect, line) in edges {
+        if !neighbour.is_some_and(|n| cage.cells.contains(&n)) {
+            frame.render_widget(Paragraph::new(line).style(style), rect);
         }
     }
+}
 
-    duplicate_found |= counter > 1;
-
-    counter = 0;
-    //check for any duplicates in the same row
-    for i in 0..BOARD_LENGTH {
-        let p = Point {
-            x: point_cords.x,
-            y: i,
-        };
-        if ui.displayed_puzzle[p.as_board_cords()]
-            == ui.displayed_puzzle[point_cords.as_board_cords()]
-        {
-            counter += 1;
+/*
+    Where each pencil mark lands in a cell, one row of `cell.width` characters for every
+    line of the cell with spaces everywhere else. Lines are centred the same way a centred
+    `Paragraph` would place them
+*/
+fn notes_layout(shape: Shape, notes: u16, cell: Rect) -> Vec<Vec<char>> {
+    let (width, height) = (cell.width as usize, cell.height as usize);
+    let mut layout = vec![vec![' '; width]; height];
+    if notes == 0 {
+        return layout;
+    }
+    for (row, line) in notes_grid(shape, notes).lines().enumerate().take(height) {
+        let line: Vec<char> = line.chars().collect();
+        let offset = (width / 2).saturating_sub(line.len() / 2);
+        for (col, c) in line.into_iter().enumerate().take(width - offset) {
+            layout[row][offset + col] = c;
         }
     }
+    layout
+}
 
-    duplicate_found |= counter > 1;
+/*
+    Lay out pencil marks as a mini grid the shape of a box, with gaps for the digits that
+    aren't noted. Boards with more than 9 digits pack them into rows of 6 to fit in a cell
+*/
+fn notes_grid(shape: Shape, notes: u16) -> String {
+    let (per_line, gap) = if shape.size() <= 9 {
+        (shape.box_cols, " ")
+    } else {
+        (6, "")
+    };
+    let digits: Vec<String> = (1..=shape.size())
+        .map(|digit| {
+            if notes & (1 << (digit - 1)) != 0 {
+                shape.symbol(digit).to_string()
+            } else {
+                " ".to_string()
+            }
+        })
+        .collect();
+    let lines: Vec<String> = digits.chunks(per_line).map(|line| line.join(gap)).collect();
+    lines.join("\n")
+}
 
-    duplicate_found
+/*
+    How much room the board takes up on screen, as (width, height)
+*/
+fn board_size(shape: Shape) -> (u16, u16) {
+    let length = shape.size() as u16;
+    (length * CELL_WIDTH, length * CELL_HEIGHT)
 }
 
 /*
     Converts the puzzles strange coordinate system into more familiar / easier to work with x and y cords
 */
-fn square_to_point_cords(square_number: usize, cell_number: usize) -> Point {
-    let col = (square_number % 3) * 3 + cell_number % 3;
-    let row = (square_number / 3) * 3 + cell_number / 3;
+fn square_to_point_cords(shape: Shape, square_number: usize, cell_number: usize) -> Point {
+    let col = (square_number % shape.box_rows) * shape.box_cols + cell_number % shape.box_cols;
+    let row = (square_number / shape.box_rows) * shape.box_rows + cell_number / shape.box_cols;
 
     Point { x: col, y: row }
 }
 
 /*
-    Helper function to take a rect and split it equally into a 3x3 rect
+    Helper function to take a rect and split it equally into `rows` by `cols` rects
 */
-fn split_rect_into_three_by_three_square(area: Rect) -> Vec<Rect> {
+fn split_rect_into_grid(area: Rect, rows: usize, cols: usize) -> Vec<Rect> {
     let mut rets = vec![];
-    let rows = split_rect_into_three(area, Direction::Vertical);
-    for row in rows {
-        rets.extend(split_rect_into_three(row, Direction::Horizontal));
+    for row in split_rect(area, Direction::Vertical, rows) {
+        rets.extend(split_rect(row, Direction::Horizontal, cols));
     }
     rets
 }
 
 /*
-    Helper function to split a rect into three equally sized Rects
+    Helper function to split a rect into `parts` equally sized Rects
 */
-fn split_rect_into_three(area: Rect, dir: Direction) -> Vec<Rect> {
+fn split_rect(area: Rect, dir: Direction, parts: usize) -> Vec<Rect> {
     Layout::default()
         .direction(dir)
-        .constraints(
-            [
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-            ]
-            .as_ref(),
-        )
+        .constraints(vec![Constraint::Ratio(1, parts as u32); parts])
         .split(area)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn pack_game() -> UI {
+        let dir = std::env::temp_dir().join("sudoku-rs-ui-test");
+        fs::create_dir_all(&dir).unwrap();
+        let line =
+            "..27.4..6......5....1.6...35.....9...4.....15.13...6..374...2....6.2549.........1";
+        fs::write(dir.join("pack.sdm"), format!("{}\n", line)).unwrap();
+
+        let pack = PuzzlePack::load(dir.join("pack.sdm").to_str().unwrap()).unwrap();
+        let puzzle = pack.current().clone();
+        let mut ui = UI::from_save(SavedGame {
+            displayed_puzzle: puzzle.puzzle.clone(),
+            notes: vec![0; puzzle.shape.cell_count()],
+            puzzle,
+            time_in_ms: 0,
+            gave_up: false,
+            has_won: false,
+            pack: None,
+            daily: None,
+        });
+        ui.pack = Some(pack);
+        ui
+    }
+
+    #[test]
+    fn giving_up_does_not_solve_pack_puzzle() {
+        // a full, correct board is seen as won on the next frame either way
+        let mut ui = pack_game();
+        ui.give_up();
+        ui.has_won = true;
+        ui.record_win();
+        assert_eq!(ui.pack.as_ref().unwrap().solved_count(), 0);
+
+        let mut ui = pack_game();
+        ui.displayed_puzzle = ui.puzzle.solution.clone();
+        ui.has_won = true;
+        ui.record_win();
+        assert_eq!(ui.pack.as_ref().unwrap().solved_count(), 1);
+    }
+}